@@ -1,72 +1,591 @@
+use std::{cmp::Reverse, fmt};
+
 use aes::{
-    cipher::{generic_array::GenericArray, BlockDecrypt, KeyInit},
-    Aes128,
+    cipher::{generic_array::GenericArray, BlockDecrypt, BlockEncrypt, KeyInit},
+    Aes128, Aes192, Aes256,
 };
 use itertools::Itertools;
 
 use crate::{
-    crack_utils::{guess_keysizes, transpose, CrackedMessage},
-    sorted::Sorted,
+    padding::{pkcs7_pad, pkcs7_unpad, PaddingError},
+    xor,
 };
 
-#[must_use]
-pub fn aes_128_ecb(key: &[u8], mut data: Vec<u8>) -> Vec<u8> {
-    let key = GenericArray::from_slice(key);
-    let cipher = Aes128::new(key);
-    for chunk in data.chunks_mut(16) {
-        cipher.decrypt_block(GenericArray::from_mut_slice(chunk));
+pub const BLOCK_SIZE: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AesError {
+    /// The key wasn't 16, 24 or 32 bytes long.
+    InvalidKeyLength(usize),
+    /// The data wasn't a multiple of the block size.
+    InvalidDataLength(usize),
+    /// The IV wasn't exactly one block long.
+    InvalidIvLength(usize),
+    /// An edit started past the end of the ciphertext.
+    InvalidOffset(usize),
+    /// The decrypted data wasn't validly padded.
+    Padding(PaddingError),
+}
+
+impl fmt::Display for AesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidKeyLength(len) => {
+                write!(f, "invalid key length {len}, expected 16, 24 or 32 bytes")
+            }
+            Self::InvalidDataLength(len) => {
+                write!(f, "data length {len} is not a multiple of {BLOCK_SIZE}")
+            }
+            Self::InvalidIvLength(len) => {
+                write!(f, "invalid IV length {len}, expected {BLOCK_SIZE} bytes")
+            }
+            Self::InvalidOffset(offset) => write!(f, "offset {offset} is past the end of the data"),
+            Self::Padding(err) => write!(f, "invalid padding: {err}"),
+        }
     }
-    data
 }
 
-fn single(bytes: &[u8]) -> Vec<CrackedMessage<u8>> {
-    (0x00..=0xFF)
-        .filter_map(|b| {
-            let cipher = Aes128::new(GenericArray::from_slice(&[b; 16]));
-            let mut data = bytes.to_owned();
-            cipher.decrypt_block(GenericArray::from_mut_slice(&mut data));
+impl std::error::Error for AesError {}
 
-            CrackedMessage::try_from((b, data)).ok()
-        })
-        .collect_vec()
-        .sorted()
+impl From<PaddingError> for AesError {
+    fn from(err: PaddingError) -> Self {
+        Self::Padding(err)
+    }
+}
+
+/// The raw AES block cipher, for whichever key size we were given.
+enum Cipher {
+    Aes128(Aes128),
+    Aes192(Aes192),
+    Aes256(Aes256),
+}
+
+impl Cipher {
+    fn new(key: &[u8]) -> Result<Self, AesError> {
+        match key.len() {
+            16 => Ok(Self::Aes128(Aes128::new(GenericArray::from_slice(key)))),
+            24 => Ok(Self::Aes192(Aes192::new(GenericArray::from_slice(key)))),
+            32 => Ok(Self::Aes256(Aes256::new(GenericArray::from_slice(key)))),
+            len => Err(AesError::InvalidKeyLength(len)),
+        }
+    }
+
+    fn encrypt_block(&self, block: &mut [u8]) {
+        let block = GenericArray::from_mut_slice(block);
+        match self {
+            Self::Aes128(cipher) => cipher.encrypt_block(block),
+            Self::Aes192(cipher) => cipher.encrypt_block(block),
+            Self::Aes256(cipher) => cipher.encrypt_block(block),
+        }
+    }
+
+    fn decrypt_block(&self, block: &mut [u8]) {
+        let block = GenericArray::from_mut_slice(block);
+        match self {
+            Self::Aes128(cipher) => cipher.decrypt_block(block),
+            Self::Aes192(cipher) => cipher.decrypt_block(block),
+            Self::Aes256(cipher) => cipher.decrypt_block(block),
+        }
+    }
+}
+
+fn check_blocks(data: &[u8]) -> Result<(), AesError> {
+    if data.len() % BLOCK_SIZE == 0 {
+        Ok(())
+    } else {
+        Err(AesError::InvalidDataLength(data.len()))
+    }
+}
+
+/// Encrypt block-aligned data with AES in ECB mode. The key may be 16, 24 or 32 bytes.
+pub fn ecb_encrypt(key: &[u8], data: &[u8]) -> Result<Vec<u8>, AesError> {
+    let cipher = Cipher::new(key)?;
+    check_blocks(data)?;
+
+    let mut data = data.to_vec();
+    for chunk in data.chunks_mut(BLOCK_SIZE) {
+        cipher.encrypt_block(chunk);
+    }
+
+    Ok(data)
+}
+
+/// Decrypt block-aligned data with AES in ECB mode. The key may be 16, 24 or 32 bytes.
+pub fn ecb_decrypt(key: &[u8], data: &[u8]) -> Result<Vec<u8>, AesError> {
+    let cipher = Cipher::new(key)?;
+    check_blocks(data)?;
+
+    let mut data = data.to_vec();
+    for chunk in data.chunks_mut(BLOCK_SIZE) {
+        cipher.decrypt_block(chunk);
+    }
+
+    Ok(data)
 }
 
+/// PKCS#7-pad the data, then encrypt it with AES in ECB mode.
+pub fn ecb_encrypt_padded(key: &[u8], data: &[u8]) -> Result<Vec<u8>, AesError> {
+    ecb_encrypt(key, &pkcs7_pad(data, BLOCK_SIZE))
+}
+
+/// Decrypt data with AES in ECB mode, then strip its PKCS#7 padding.
+pub fn ecb_decrypt_padded(key: &[u8], data: &[u8]) -> Result<Vec<u8>, AesError> {
+    Ok(pkcs7_unpad(&ecb_decrypt(key, data)?, BLOCK_SIZE)?)
+}
+
+fn check_iv(iv: &[u8]) -> Result<(), AesError> {
+    if iv.len() == BLOCK_SIZE {
+        Ok(())
+    } else {
+        Err(AesError::InvalidIvLength(iv.len()))
+    }
+}
+
+/// Encrypt block-aligned data with AES in CBC mode, without adding any padding.
+///
+/// Each plaintext block is XORed with the previous ciphertext block (or the IV, for the first
+/// one) before being run through the block cipher.
+pub fn cbc_encrypt_unpadded(key: &[u8], iv: &[u8], data: &[u8]) -> Result<Vec<u8>, AesError> {
+    let cipher = Cipher::new(key)?;
+    check_iv(iv)?;
+    check_blocks(data)?;
+
+    let mut encrypted = Vec::with_capacity(data.len());
+    let mut previous = iv.to_vec();
+    for chunk in data.chunks(BLOCK_SIZE) {
+        let mut block = xor::fixed(chunk, &previous).expect("Blocks have the same length");
+        cipher.encrypt_block(&mut block);
+        encrypted.extend_from_slice(&block);
+        previous = block;
+    }
+
+    Ok(encrypted)
+}
+
+/// Decrypt block-aligned data with AES in CBC mode, leaving any padding in place.
+pub fn cbc_decrypt_unpadded(key: &[u8], iv: &[u8], data: &[u8]) -> Result<Vec<u8>, AesError> {
+    let cipher = Cipher::new(key)?;
+    check_iv(iv)?;
+    check_blocks(data)?;
+
+    let mut decrypted = Vec::with_capacity(data.len());
+    let mut previous = iv;
+    for chunk in data.chunks(BLOCK_SIZE) {
+        let mut block = chunk.to_vec();
+        cipher.decrypt_block(&mut block);
+        decrypted.extend(xor::fixed(&block, previous).expect("Blocks have the same length"));
+        previous = chunk;
+    }
+
+    Ok(decrypted)
+}
+
+/// PKCS#7-pad the data, then encrypt it with AES in CBC mode.
+pub fn cbc_encrypt(key: &[u8], iv: &[u8], data: &[u8]) -> Result<Vec<u8>, AesError> {
+    cbc_encrypt_unpadded(key, iv, &pkcs7_pad(data, BLOCK_SIZE))
+}
+
+/// Decrypt data with AES in CBC mode, then strip its PKCS#7 padding.
+pub fn cbc_decrypt(key: &[u8], iv: &[u8], data: &[u8]) -> Result<Vec<u8>, AesError> {
+    Ok(pkcs7_unpad(
+        &cbc_decrypt_unpadded(key, iv, data)?,
+        BLOCK_SIZE,
+    )?)
+}
+
+/// Encrypt with AES-CBC, using the key as the IV as well. Don't: anyone who can see a
+/// decryption of a tampered ciphertext can recover the key.
+pub fn cbc_encrypt_key_as_iv(key: &[u8], data: &[u8]) -> Result<Vec<u8>, AesError> {
+    cbc_encrypt(key, key, data)
+}
+
+/// Decrypt AES-CBC which used the key as the IV.
+pub fn cbc_decrypt_key_as_iv(key: &[u8], data: &[u8]) -> Result<Vec<u8>, AesError> {
+    cbc_decrypt(key, key, data)
+}
+
+/// How the counter block fed to the cipher is built in CTR mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CtrCounter {
+    /// A 64-bit little-endian nonce followed by a 64-bit little-endian block count, as used by
+    /// Cryptopals.
+    LittleEndian { nonce: u64 },
+    /// A single 128-bit big-endian counter starting at the given value and wrapping on overflow,
+    /// as used by NIST SP 800-38A.
+    BigEndian { initial: u128 },
+}
+
+impl CtrCounter {
+    fn block(&self, index: u64) -> [u8; BLOCK_SIZE] {
+        let mut block = [0; BLOCK_SIZE];
+        match self {
+            Self::LittleEndian { nonce } => {
+                block[..8].copy_from_slice(&nonce.to_le_bytes());
+                block[8..].copy_from_slice(&index.to_le_bytes());
+            }
+            Self::BigEndian { initial } => {
+                block.copy_from_slice(&initial.wrapping_add(u128::from(index)).to_be_bytes());
+            }
+        }
+
+        block
+    }
+}
+
+/// Generate `len` bytes of CTR keystream, starting `offset` bytes into the stream. Only the
+/// blocks covering the requested range are generated.
+pub fn ctr_keystream(
+    key: &[u8],
+    counter: CtrCounter,
+    offset: usize,
+    len: usize,
+) -> Result<Vec<u8>, AesError> {
+    let cipher = Cipher::new(key)?;
+    let skip = offset % BLOCK_SIZE;
+
+    let mut keystream = Vec::with_capacity(len + BLOCK_SIZE);
+    let mut index = (offset / BLOCK_SIZE) as u64;
+    while keystream.len() < skip + len {
+        let mut block = counter.block(index);
+        cipher.encrypt_block(&mut block);
+        keystream.extend_from_slice(&block);
+        index += 1;
+    }
+
+    Ok(keystream[skip..skip + len].to_vec())
+}
+
+/// Encrypt or decrypt (they're the same operation) data which sits `offset` bytes into an
+/// AES-CTR stream.
+pub fn ctr_at(
+    key: &[u8],
+    counter: CtrCounter,
+    offset: usize,
+    data: &[u8],
+) -> Result<Vec<u8>, AesError> {
+    let keystream = ctr_keystream(key, counter, offset, data.len())?;
+
+    Ok(xor::fixed(data, &keystream).expect("Keystream has the same length as the data"))
+}
+
+/// Encrypt or decrypt data with AES in CTR mode.
+pub fn ctr(key: &[u8], counter: CtrCounter, data: &[u8]) -> Result<Vec<u8>, AesError> {
+    ctr_at(key, counter, 0, data)
+}
+
+/// Replace the plaintext at `offset` of an AES-CTR ciphertext with `newtext`, without
+/// decrypting the rest of it. Only the keystream covering the new text is generated. The new
+/// text may run past the end of the ciphertext, which then grows to fit it.
+pub fn ctr_edit(
+    key: &[u8],
+    counter: CtrCounter,
+    ciphertext: &[u8],
+    offset: usize,
+    newtext: &[u8],
+) -> Result<Vec<u8>, AesError> {
+    if offset > ciphertext.len() {
+        return Err(AesError::InvalidOffset(offset));
+    }
+
+    let mut edited = ciphertext.to_vec();
+    edited.resize(ciphertext.len().max(offset + newtext.len()), 0);
+    edited[offset..offset + newtext.len()].copy_from_slice(&ctr_at(key, counter, offset, newtext)?);
+
+    Ok(edited)
+}
+
+/// Find the blocks which appear more than once in the data. Each entry lists the byte offsets
+/// at which one repeated block occurs.
 #[must_use]
-pub fn aes_crack(input: &[u8], min: usize, max: usize) -> Vec<CrackedMessage<Vec<u8>>> {
-    guess_keysizes(input, min, max)
+pub fn repeated_blocks(data: &[u8]) -> Vec<Vec<usize>> {
+    let mut offsets: Vec<(&[u8], Vec<usize>)> = Vec::new();
+
+    for (i, block) in data.chunks_exact(BLOCK_SIZE).enumerate() {
+        match offsets.iter_mut().find(|(b, _)| *b == block) {
+            Some((_, found)) => found.push(i * BLOCK_SIZE),
+            None => offsets.push((block, vec![i * BLOCK_SIZE])),
+        }
+    }
+
+    offsets
         .into_iter()
-        .filter_map(|keysize| {
-            let key: Vec<_> = transpose(input, keysize)
-                .into_iter()
-                .filter_map(|block| single(&block).first().map(|res| res.key))
-                .collect();
-
-            if key.len() != keysize {
-                return None;
-            }
+        .map(|(_, found)| found)
+        .filter(|found| found.len() > 1)
+        .collect()
+}
+
+/// A ciphertext which may have been encrypted with ECB, judged by its repeated blocks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EcbCandidate {
+    /// The index of the ciphertext in the input.
+    pub index: usize,
+    /// How many blocks are copies of an earlier block.
+    pub repetitions: usize,
+    /// The byte offsets of each repeated block, as returned by `repeated_blocks`.
+    pub duplicates: Vec<Vec<usize>>,
+}
 
-            let data = input.to_vec();
-            let decoded = aes_128_ecb(&key, data);
+/// Rank ciphertexts by how likely they are to have been encrypted with ECB. Identical plaintext
+/// blocks always produce identical ciphertext blocks in ECB, so the ciphertexts with the most
+/// repeated blocks come first.
+#[must_use]
+pub fn detect_ecb<T: AsRef<[u8]>>(ciphertexts: &[T]) -> Vec<EcbCandidate> {
+    let mut candidates = ciphertexts
+        .iter()
+        .enumerate()
+        .map(|(index, ciphertext)| {
+            let duplicates = repeated_blocks(ciphertext.as_ref());
+            let repetitions = duplicates.iter().map(|found| found.len() - 1).sum();
 
-            CrackedMessage::try_from((key, decoded)).ok()
+            EcbCandidate {
+                index,
+                repetitions,
+                duplicates,
+            }
         })
-        .collect_vec()
-        .sorted()
+        .collect_vec();
+
+    candidates.sort_by_key(|c| Reverse(c.repetitions));
+
+    candidates
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::hex;
+    use test_case::test_case;
+
     #[test]
-    fn test_aes_128_ecb() {
+    fn test_ecb_decrypt() {
         let key = "YELLOW SUBMARINE";
         let string = include_str!("../data/7.txt");
 
-        let decrypted = aes_128_ecb(key.as_bytes(), crate::base64::decode(string));
+        let decrypted = ecb_decrypt(key.as_bytes(), &crate::base64::decode(string)).unwrap();
         let decrypted_str = std::str::from_utf8(&decrypted).unwrap();
 
         assert_eq!("I'm back and I'm ringin' the bell \nA rockin' on the mike while the fly girls yell \nIn ecstasy in the back of me \nWell that's my DJ Deshay cuttin' all them Z's \nHittin' hard and the girlies goin' crazy \nVanilla's on the mike, man I'm not lazy. \n\nI'm lettin' my drug kick in \nIt controls my mouth and I begin \nTo just let it flow, let my concepts go \nMy posse's to the side yellin', Go Vanilla Go! \n\nSmooth 'cause that's the way I will be \nAnd if you don't give a damn, then \nWhy you starin' at me \nSo get off 'cause I control the stage \nThere's no dissin' allowed \nI'm in my own phase \nThe girlies sa y they love me and that is ok \nAnd I can dance better than any kid n' play \n\nStage 2 -- Yea the one ya' wanna listen to \nIt's off my head so let the beat play through \nSo I can funk it up and make it sound good \n1-2-3 Yo -- Knock on some wood \nFor good luck, I like my rhymes atrocious \nSupercalafragilisticexpialidocious \nI'm an effect and that you can bet \nI can take a fly girl and make her wet. \n\nI'm like Samson -- Samson to Delilah \nThere's no denyin', You can try to hang \nBut you'll keep tryin' to get my style \nOver and over, practice makes perfect \nBut not if you're a loafer. \n\nYou'll get nowhere, no place, no time, no girls \nSoon -- Oh my God, homebody, you probably eat \nSpaghetti with a spoon! Come on and say it! \n\nVIP. Vanilla Ice yep, yep, I'm comin' hard like a rhino \nIntoxicating so you stagger like a wino \nSo punks stop trying and girl stop cryin' \nVanilla Ice is sellin' and you people are buyin' \n'Cause why the freaks are jockin' like Crazy Glue \nMovin' and groovin' trying to sing along \nAll through the ghetto groovin' this here song \nNow you're amazed by the VIP posse. \n\nSteppin' so hard like a German Nazi \nStartled by the bases hittin' ground \nThere's no trippin' on mine, I'm just gettin' down \nSparkamatic, I'm hangin' tight like a fanatic \nYou trapped me once and I thought that \nYou might have it \nSo step down and lend me your ear \n'89 in my time! You, '90 is my year. \n\nYou're weakenin' fast, YO! and I can tell it \nYour body's gettin' hot, so, so I can smell it \nSo don't be mad and don't be sad \n'Cause the lyrics belong to ICE, You can call me Dad \nYou're pitchin' a fit, so step back and endure \nLet the witch doctor, Ice, do the dance to cure \nSo come up close and don't be square \nYou wanna battle me -- Anytime, anywhere \n\nYou thought that I was weak, Boy, you're dead wrong \nSo come on, everybody and sing this song \n\nSay -- Play that funky music Say, go white boy, go white boy go \nplay that funky music Go white boy, go white boy, go \nLay down and boogie and play that funky music till you die. \n\nPlay that funky music Come on, Come on, let me hear \nPlay that funky music white boy you say it, say it \nPlay that funky music A little louder now \nPlay that funky music, white boy Come on, Come on, Come on \nPlay that funky music \n\u{4}\u{4}\u{4}\u{4}", decrypted_str);
     }
+
+    #[test]
+    fn test_ecb_round_trip() {
+        let key = b"YELLOW SUBMARINE";
+        let ciphertext = crate::base64::decode(include_str!("../data/7.txt"));
+
+        let plaintext = ecb_decrypt(key, &ciphertext).unwrap();
+
+        assert_eq!(ciphertext, ecb_encrypt(key, &plaintext).unwrap());
+    }
+
+    #[test]
+    fn test_ecb_decrypt_padded() {
+        let key = b"YELLOW SUBMARINE";
+        let ciphertext = crate::base64::decode(include_str!("../data/7.txt"));
+
+        let decrypted = ecb_decrypt_padded(key, &ciphertext).unwrap();
+
+        assert!(decrypted.ends_with(b"Play that funky music \n"));
+        assert_eq!(ciphertext, ecb_encrypt_padded(key, &decrypted).unwrap());
+    }
+
+    #[test]
+    fn test_ecb_decrypt_padded_rejects_bad_padding() {
+        let key = b"YELLOW SUBMARINE";
+        let ciphertext = ecb_encrypt(key, b"YELLOW SUBMARIN\x02").unwrap();
+
+        assert_eq!(
+            Err(AesError::Padding(PaddingError::InconsistentPadding)),
+            ecb_decrypt_padded(key, &ciphertext)
+        );
+    }
+
+    // FIPS-197, appendix C
+    #[test_case("000102030405060708090a0b0c0d0e0f", "69c4e0d86a7b0430d8cdb78070b4c55a"; "AES-128")]
+    #[test_case("000102030405060708090a0b0c0d0e0f1011121314151617", "dda97ca4864cdfe06eaf70a0ec0d7191"; "AES-192")]
+    #[test_case("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f", "8ea2b7ca516745bfeafc49904b496089"; "AES-256")]
+    fn test_ecb_key_sizes(key: &str, expected: &str) {
+        let key = hex::decode(key);
+        let plaintext = hex::decode("00112233445566778899aabbccddeeff");
+
+        let ciphertext = ecb_encrypt(&key, &plaintext).unwrap();
+
+        assert_eq!(expected, hex::encode(&ciphertext));
+        assert_eq!(plaintext, ecb_decrypt(&key, &ciphertext).unwrap());
+    }
+
+    #[test]
+    fn test_ecb_rejects_bad_key_length() {
+        assert_eq!(
+            Err(AesError::InvalidKeyLength(15)),
+            ecb_encrypt(b"YELLOW SUBMARIN", &[0; 16])
+        );
+    }
+
+    #[test]
+    fn test_ecb_rejects_unaligned_data() {
+        assert_eq!(
+            Err(AesError::InvalidDataLength(17)),
+            ecb_decrypt(b"YELLOW SUBMARINE", &[0; 17])
+        );
+    }
+
+    // NIST SP 800-38A, F.2.1 to F.2.6
+    #[test_case("2b7e151628aed2a6abf7158809cf4f3c", "7649abac8119b246cee98e9b12e9197d5086cb9b507219ee95db113a917678b273bed6b8e3c1743b7116e69e222295163ff1caa1681fac09120eca307586e1a7"; "CBC-AES128")]
+    #[test_case("8e73b0f7da0e6452c810f32b809079e562f8ead2522c6b7b", "4f021db243bc633d7178183a9fa071e8b4d9ada9ad7dedf4e5e738763f69145a571b242012fb7ae07fa9baac3df102e008b0e27988598881d920a9e64f5615cd"; "CBC-AES192")]
+    #[test_case("603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4", "f58c4c04d6e5f1ba779eabfb5f7bfbd69cfc4e967edb808d679f777bc6702c7d39f23369a9d9bacfa530e26304231461b2eb05e2c39be9fcda6c19078c6a9d1b"; "CBC-AES256")]
+    fn test_cbc_nist_vectors(key: &str, expected: &str) {
+        let key = hex::decode(key);
+        let iv = hex::decode("000102030405060708090a0b0c0d0e0f");
+        let plaintext = hex::decode("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e5130c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710");
+
+        let ciphertext = cbc_encrypt_unpadded(&key, &iv, &plaintext).unwrap();
+
+        assert_eq!(expected, hex::encode(&ciphertext));
+        assert_eq!(
+            plaintext,
+            cbc_decrypt_unpadded(&key, &iv, &ciphertext).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_cbc_round_trip() {
+        let key = b"YELLOW SUBMARINE";
+        let iv = [7; BLOCK_SIZE];
+
+        for len in 0..40 {
+            let plaintext = vec![b'A'; len];
+            let ciphertext = cbc_encrypt(key, &iv, &plaintext).unwrap();
+
+            assert_eq!(0, ciphertext.len() % BLOCK_SIZE);
+            assert_eq!(plaintext, cbc_decrypt(key, &iv, &ciphertext).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_cbc_rejects_bad_iv_length() {
+        assert_eq!(
+            Err(AesError::InvalidIvLength(8)),
+            cbc_encrypt(b"YELLOW SUBMARINE", &[0; 8], b"data")
+        );
+    }
+
+    #[test]
+    fn test_ctr_little_endian() {
+        let ciphertext = crate::base64::decode(
+            "L77na/nrFsKvynd6HzOoG7GHTLXsTVu9qvY/2syLXzhPweyyMTJULu/6/kXX0KSvoOLSFQ==",
+        );
+
+        let plaintext = ctr(
+            b"YELLOW SUBMARINE",
+            CtrCounter::LittleEndian { nonce: 0 },
+            &ciphertext,
+        )
+        .unwrap();
+
+        assert_eq!(
+            "Yo, VIP Let's kick it Ice, Ice, baby Ice, Ice, baby ",
+            String::from_utf8_lossy(&plaintext)
+        );
+    }
+
+    // NIST SP 800-38A, F.5.1
+    #[test]
+    fn test_ctr_big_endian_nist_vector() {
+        let key = hex::decode("2b7e151628aed2a6abf7158809cf4f3c");
+        let counter = CtrCounter::BigEndian {
+            initial: 0xf0f1f2f3f4f5f6f7f8f9fafbfcfdfeff,
+        };
+        let plaintext = hex::decode("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e5130c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710");
+
+        let ciphertext = ctr(&key, counter, &plaintext).unwrap();
+
+        assert_eq!("874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff5ae4df3edbd5d35e5b4f09020db03eab1e031dda2fbe03d1792170a0f3009cee", hex::encode(&ciphertext));
+    }
+
+    #[test]
+    fn test_ctr_big_endian_wraps() {
+        let key = b"YELLOW SUBMARINE";
+
+        let wrapped = ctr_keystream(key, CtrCounter::BigEndian { initial: u128::MAX }, 16, 16);
+        let zero = ctr_keystream(key, CtrCounter::BigEndian { initial: 0 }, 0, 16);
+
+        assert_eq!(zero, wrapped);
+    }
+
+    #[test]
+    fn test_ctr_at_offset() {
+        let key = b"YELLOW SUBMARINE";
+        let counter = CtrCounter::LittleEndian { nonce: 42 };
+        let plaintext = b"We all live in a yellow submarine, a yellow submarine".to_vec();
+
+        let ciphertext = ctr(key, counter, &plaintext).unwrap();
+
+        for offset in 0..plaintext.len() {
+            assert_eq!(
+                ciphertext[offset..],
+                ctr_at(key, counter, offset, &plaintext[offset..]).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_repeated_blocks() {
+        let data = b"YELLOW SUBMARINEAAAAAAAAAAAAAAAAYELLOW SUBMARINEYELLOW SUBMARINE";
+
+        assert_eq!(vec![vec![0, 32, 48]], repeated_blocks(data));
+        assert!(repeated_blocks(b"YELLOW SUBMARINE").is_empty());
+    }
+
+    #[test]
+    fn test_detect_ecb() {
+        let key = b"YELLOW SUBMARINE";
+        let plaintext = [b"A".repeat(32), b"B".repeat(16)].concat();
+        let ciphertexts = vec![
+            cbc_encrypt(key, &[0; BLOCK_SIZE], &plaintext).unwrap(),
+            ecb_encrypt(key, &plaintext).unwrap(),
+        ];
+
+        let candidates = detect_ecb(&ciphertexts);
+
+        assert_eq!(
+            EcbCandidate {
+                index: 1,
+                repetitions: 1,
+                duplicates: vec![vec![0, 16]],
+            },
+            candidates[0]
+        );
+        assert_eq!(0, candidates[1].repetitions);
+    }
+
+    #[test]
+    fn test_ctr_edit() {
+        let key = b"YELLOW SUBMARINE";
+        let counter = CtrCounter::LittleEndian { nonce: 0 };
+        let ciphertext = ctr(key, counter, b"We all live in a yellow submarine").unwrap();
+
+        let edited = ctr_edit(key, counter, &ciphertext, 17, b"purple").unwrap();
+        assert_eq!(
+            b"We all live in a purple submarine".to_vec(),
+            ctr(key, counter, &edited).unwrap()
+        );
+
+        let extended = ctr_edit(key, counter, &ciphertext, 33, b"!").unwrap();
+        assert_eq!(
+            b"We all live in a yellow submarine!".to_vec(),
+            ctr(key, counter, &extended).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_ctr_edit_rejects_offset_past_end() {
+        let key = b"YELLOW SUBMARINE";
+        let counter = CtrCounter::LittleEndian { nonce: 0 };
+
+        assert_eq!(
+            Err(AesError::InvalidOffset(5)),
+            ctr_edit(key, counter, &[0; 4], 5, b"a")
+        );
+    }
 }
@@ -50,7 +50,7 @@ pub fn decode(string: &str) -> Vec<u8> {
             ALPHABET_TABLE
                 .iter()
                 .enumerate()
-                .find_map(|(i, t)| (*t == b).then(|| i as u8))
+                .find_map(|(i, t)| (*t == b).then_some(i as u8))
         })
         .collect();
 
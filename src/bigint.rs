@@ -0,0 +1,737 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    ops::{Add, Div, Mul, Rem, Shl, Shr, Sub},
+    str::FromStr,
+};
+
+/// An arbitrary-precision unsigned integer, stored as little-endian 32-bit limbs with no
+/// high zero limbs (so zero has no limbs at all, and every value has exactly one form).
+///
+/// The arithmetic operators work on references as well as values. Like the primitive types,
+/// subtraction panics on underflow and division panics on a zero divisor.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    #[must_use]
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn one() -> Self {
+        Self::from(1u32)
+    }
+
+    fn from_limbs(mut limbs: Vec<u32>) -> Self {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+
+        Self { limbs }
+    }
+
+    /// Read a big-endian byte string, the way `hex::decode` gives numbers out.
+    #[must_use]
+    pub fn from_bytes_be(bytes: &[u8]) -> Self {
+        let limbs = bytes
+            .rchunks(4)
+            .map(|chunk| chunk.iter().fold(0, |limb, &b| limb << 8 | u32::from(b)))
+            .collect();
+
+        Self::from_limbs(limbs)
+    }
+
+    /// The shortest big-endian byte string for this number, which for zero is a single zero
+    /// byte.
+    #[must_use]
+    pub fn to_bytes_be(&self) -> Vec<u8> {
+        let bytes: Vec<_> = self
+            .limbs
+            .iter()
+            .rev()
+            .flat_map(|limb| limb.to_be_bytes())
+            .skip_while(|&b| b == 0)
+            .collect();
+
+        if bytes.is_empty() {
+            vec![0]
+        } else {
+            bytes
+        }
+    }
+
+    /// The value as a `u128`, if it fits.
+    #[must_use]
+    pub fn to_u128(&self) -> Option<u128> {
+        if self.limbs.len() > 4 {
+            return None;
+        }
+
+        Some(
+            self.limbs
+                .iter()
+                .rev()
+                .fold(0, |n, &limb| n << 32 | u128::from(limb)),
+        )
+    }
+
+    #[must_use]
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// The number of bits needed to write the number down, which is zero for zero.
+    #[must_use]
+    pub fn bits(&self) -> usize {
+        self.limbs.last().map_or(0, |top| {
+            32 * self.limbs.len() - top.leading_zeros() as usize
+        })
+    }
+
+    #[must_use]
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        if *self < *other {
+            return None;
+        }
+
+        let mut limbs = Vec::with_capacity(self.limbs.len());
+        let mut borrow = false;
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let b = other.limbs.get(i).copied().unwrap_or(0);
+            let (diff, under) = a.overflowing_sub(b);
+            let (diff, under_borrow) = diff.overflowing_sub(u32::from(borrow));
+            limbs.push(diff);
+            borrow = under || under_borrow;
+        }
+
+        Some(Self::from_limbs(limbs))
+    }
+
+    /// Quotient and remainder together, using Knuth's algorithm D.
+    ///
+    /// # Panics
+    ///
+    /// If `divisor` is zero.
+    #[must_use]
+    pub fn divrem(&self, divisor: &Self) -> (Self, Self) {
+        assert!(!divisor.is_zero(), "Attempt to divide by zero");
+
+        if self < divisor {
+            return (Self::zero(), self.clone());
+        }
+        if let [d] = divisor.limbs[..] {
+            let (quotient, remainder) = divrem_limb(&self.limbs, d);
+            return (Self::from_limbs(quotient), Self::from(remainder));
+        }
+
+        let (quotient, remainder) = divrem_knuth(&self.limbs, &divisor.limbs);
+        (Self::from_limbs(quotient), Self::from_limbs(remainder))
+    }
+
+    /// Raise to a small power.
+    #[must_use]
+    pub fn pow(&self, mut exponent: u32) -> Self {
+        let mut result = Self::one();
+        let mut base = self.clone();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = &result * &base;
+            }
+            base = &base * &base;
+            exponent >>= 1;
+        }
+
+        result
+    }
+
+    /// `self ^ exponent mod modulus`, by square-and-multiply from the top bit down.
+    ///
+    /// # Panics
+    ///
+    /// If `modulus` is zero.
+    #[must_use]
+    pub fn modpow(&self, exponent: &Self, modulus: &Self) -> Self {
+        let base = self % modulus;
+        let mut result = Self::one() % modulus;
+        for bit in (0..exponent.bits()).rev() {
+            result = &(&result * &result) % modulus;
+            if exponent.bit(bit) {
+                result = &(&result * &base) % modulus;
+            }
+        }
+
+        result
+    }
+
+    /// The `x` with `self * x = 1 mod modulus`, if there is one (that is, if `self` and
+    /// `modulus` are coprime). This is the extended Euclidean algorithm, keeping the
+    /// coefficients reduced mod `modulus` so that they never go negative.
+    ///
+    /// # Panics
+    ///
+    /// If `modulus` is zero.
+    #[must_use]
+    pub fn modinv(&self, modulus: &Self) -> Option<Self> {
+        // Throughout, self * t = r mod modulus
+        let (mut r0, mut r1) = (modulus.clone(), self % modulus);
+        let (mut t0, mut t1) = (Self::zero(), Self::one());
+
+        while !r1.is_zero() {
+            let (q, r2) = r0.divrem(&r1);
+            let t2 = &(&t0 + modulus - &(&q * &t1) % modulus) % modulus;
+            (r0, r1) = (r1, r2);
+            (t0, t1) = (t1, t2);
+        }
+
+        (r0 == Self::one()).then(|| &t0 % modulus)
+    }
+
+    /// The largest `x` with `x ^ n <= self`, by Newton's method, starting above the root and
+    /// stepping down until it stops decreasing.
+    ///
+    /// # Panics
+    ///
+    /// If `n` is zero.
+    #[must_use]
+    pub fn nth_root(&self, n: u32) -> Self {
+        assert!(n > 0, "There is no zeroth root");
+
+        if self.is_zero() || n == 1 {
+            return self.clone();
+        }
+
+        let n_big = Self::from(n);
+        let n_less_one = Self::from(n - 1);
+        let mut x = Self::one() << self.bits().div_ceil(n as usize);
+        loop {
+            let y = &(&(&n_less_one * &x) + &(self / &x.pow(n - 1))) / &n_big;
+            if y >= x {
+                return x;
+            }
+            x = y;
+        }
+    }
+
+    fn bit(&self, index: usize) -> bool {
+        self.limbs
+            .get(index / 32)
+            .is_some_and(|limb| limb >> (index % 32) & 1 == 1)
+    }
+}
+
+/// Divide by a single limb, returning the quotient's limbs and the remainder.
+fn divrem_limb(dividend: &[u32], divisor: u32) -> (Vec<u32>, u32) {
+    let divisor = u64::from(divisor);
+    let mut quotient = vec![0; dividend.len()];
+    let mut remainder = 0;
+    for (q, &limb) in quotient.iter_mut().zip(dividend).rev() {
+        let current = remainder << 32 | u64::from(limb);
+        *q = (current / divisor) as u32;
+        remainder = current % divisor;
+    }
+
+    (quotient, remainder as u32)
+}
+
+/// Long division of `u` by a `v` of at least two limbs, as in Knuth's TAOCP 4.3.1 (following
+/// the version in Hacker's Delight). Both are normalised first so that `v`'s top limb has its
+/// high bit set, which makes each quotient digit estimate off by at most two.
+fn divrem_knuth(u: &[u32], v: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    const BASE: u64 = 1 << 32;
+
+    let n = v.len();
+    let m = u.len() - n;
+    let shift = v[n - 1].leading_zeros();
+    let vn = shift_limbs_left(v, shift, n);
+    let mut un = shift_limbs_left(u, shift, u.len() + 1);
+    let mut quotient = vec![0; m + 1];
+
+    for j in (0..=m).rev() {
+        let top = u64::from(un[j + n]) << 32 | u64::from(un[j + n - 1]);
+        let mut qhat = top / u64::from(vn[n - 1]);
+        let mut rhat = top % u64::from(vn[n - 1]);
+        while qhat >= BASE || qhat * u64::from(vn[n - 2]) > (rhat << 32 | u64::from(un[j + n - 2]))
+        {
+            qhat -= 1;
+            rhat += u64::from(vn[n - 1]);
+            if rhat >= BASE {
+                break;
+            }
+        }
+
+        // Subtract qhat * v from the current window of u
+        let mut borrow: i64 = 0;
+        for i in 0..n {
+            let product = qhat * u64::from(vn[i]);
+            let t = i64::from(un[i + j]) - borrow - (product & 0xffff_ffff) as i64;
+            un[i + j] = t as u32;
+            borrow = (product >> 32) as i64 - (t >> 32);
+        }
+        let t = i64::from(un[j + n]) - borrow;
+        un[j + n] = t as u32;
+
+        // qhat was one too big, so add v back
+        if t < 0 {
+            qhat -= 1;
+            let mut carry = 0;
+            for i in 0..n {
+                let sum = u64::from(un[i + j]) + u64::from(vn[i]) + carry;
+                un[i + j] = sum as u32;
+                carry = sum >> 32;
+            }
+            un[j + n] = un[j + n].wrapping_add(carry as u32);
+        }
+        quotient[j] = qhat as u32;
+    }
+
+    let remainder = (0..n)
+        .map(|i| {
+            if shift == 0 {
+                un[i]
+            } else {
+                un[i] >> shift | un[i + 1] << (32 - shift)
+            }
+        })
+        .collect();
+
+    (quotient, remainder)
+}
+
+/// Shift limbs left by fewer than 32 bits into a vector of `len` limbs.
+fn shift_limbs_left(limbs: &[u32], shift: u32, len: usize) -> Vec<u32> {
+    let mut shifted = vec![0; len];
+    let mut carry = 0;
+    for (s, &limb) in shifted.iter_mut().zip(limbs) {
+        *s = limb << shift | carry;
+        carry = if shift == 0 { 0 } else { limb >> (32 - shift) };
+    }
+    if let Some(s) = shifted.get_mut(limbs.len()) {
+        *s = carry;
+    }
+
+    shifted
+}
+
+impl From<u32> for BigUint {
+    fn from(n: u32) -> Self {
+        Self::from_limbs(vec![n])
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(n: u64) -> Self {
+        Self::from(u128::from(n))
+    }
+}
+
+impl From<u128> for BigUint {
+    fn from(n: u128) -> Self {
+        Self::from_limbs((0..4).map(|i| (n >> (32 * i)) as u32).collect())
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: Self) -> BigUint {
+        let (long, short) = if self.limbs.len() >= other.limbs.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+
+        let mut limbs = Vec::with_capacity(long.limbs.len() + 1);
+        let mut carry = 0;
+        for (i, &a) in long.limbs.iter().enumerate() {
+            let b = short.limbs.get(i).copied().unwrap_or(0);
+            let sum = u64::from(a) + u64::from(b) + carry;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        limbs.push(carry as u32);
+
+        BigUint::from_limbs(limbs)
+    }
+}
+
+impl Sub for &BigUint {
+    type Output = BigUint;
+
+    fn sub(self, other: Self) -> BigUint {
+        self.checked_sub(other)
+            .expect("Attempt to subtract with overflow")
+    }
+}
+
+impl Mul for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: Self) -> BigUint {
+        let mut limbs = vec![0; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let product = u64::from(a) * u64::from(b) + u64::from(limbs[i + j]) + carry;
+                limbs[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+
+        BigUint::from_limbs(limbs)
+    }
+}
+
+impl Div for &BigUint {
+    type Output = BigUint;
+
+    fn div(self, other: Self) -> BigUint {
+        self.divrem(other).0
+    }
+}
+
+impl Rem for &BigUint {
+    type Output = BigUint;
+
+    fn rem(self, other: Self) -> BigUint {
+        self.divrem(other).1
+    }
+}
+
+/// Implement an operator on values in terms of the one on references.
+macro_rules! forward_by_value {
+    ($($trait:ident $method:ident),*) => {$(
+        impl $trait for BigUint {
+            type Output = BigUint;
+
+            fn $method(self, other: Self) -> BigUint {
+                (&self).$method(&other)
+            }
+        }
+
+        impl $trait<&BigUint> for BigUint {
+            type Output = BigUint;
+
+            fn $method(self, other: &BigUint) -> BigUint {
+                (&self).$method(other)
+            }
+        }
+    )*};
+}
+
+forward_by_value!(Add add, Sub sub, Mul mul, Div div, Rem rem);
+
+impl Shl<usize> for &BigUint {
+    type Output = BigUint;
+
+    fn shl(self, shift: usize) -> BigUint {
+        let mut limbs = vec![0; shift / 32];
+        limbs.extend(shift_limbs_left(
+            &self.limbs,
+            (shift % 32) as u32,
+            self.limbs.len() + 1,
+        ));
+
+        BigUint::from_limbs(limbs)
+    }
+}
+
+impl Shl<usize> for BigUint {
+    type Output = BigUint;
+
+    fn shl(self, shift: usize) -> BigUint {
+        &self << shift
+    }
+}
+
+impl Shr<usize> for &BigUint {
+    type Output = BigUint;
+
+    fn shr(self, shift: usize) -> BigUint {
+        let limbs = self.limbs.get(shift / 32..).unwrap_or_default();
+        let bits = (shift % 32) as u32;
+        let shifted = (0..limbs.len())
+            .map(|i| {
+                let high = limbs.get(i + 1).copied().unwrap_or(0);
+                if bits == 0 {
+                    limbs[i]
+                } else {
+                    limbs[i] >> bits | high << (32 - bits)
+                }
+            })
+            .collect();
+
+        BigUint::from_limbs(shifted)
+    }
+}
+
+impl Shr<usize> for BigUint {
+    type Output = BigUint;
+
+    fn shr(self, shift: usize) -> BigUint {
+        &self >> shift
+    }
+}
+
+/// The largest power of ten that fits in a limb, for converting to and from decimal nine
+/// digits at a time.
+const DECIMAL_CHUNK: u32 = 1_000_000_000;
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut chunks = Vec::new();
+        let mut rest = self.limbs.clone();
+        while !rest.is_empty() {
+            let (quotient, remainder) = divrem_limb(&rest, DECIMAL_CHUNK);
+            chunks.push(remainder);
+            rest = Self::from_limbs(quotient).limbs;
+        }
+
+        match chunks.split_last() {
+            None => write!(f, "0"),
+            Some((top, rest)) => {
+                write!(f, "{top}")?;
+                rest.iter()
+                    .rev()
+                    .try_for_each(|chunk| write!(f, "{chunk:09}"))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseBigUintError;
+
+impl fmt::Display for ParseBigUintError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Not a decimal number")
+    }
+}
+
+impl std::error::Error for ParseBigUintError {}
+
+impl FromStr for BigUint {
+    type Err = ParseBigUintError;
+
+    /// Parse a decimal number.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseBigUintError);
+        }
+
+        let ten = Self::from(10u32);
+        Ok(s.bytes().fold(Self::zero(), |n, digit| {
+            &(&n * &ten) + &Self::from(u32::from(digit - b'0'))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::{RngCore, SplitMix64};
+
+    /// Random numbers of random sizes, so that small values, limb boundaries and full-width
+    /// values all turn up.
+    fn random_u128s(seed: u64, count: usize) -> Vec<u128> {
+        let mut rng = SplitMix64::new(seed);
+        (0..count)
+            .map(|_| {
+                let n = u128::from(rng.next_u64()) << 64 | u128::from(rng.next_u64());
+                n >> rng.gen_range(0..128)
+            })
+            .collect()
+    }
+
+    fn big(n: u128) -> BigUint {
+        BigUint::from(n)
+    }
+
+    #[test]
+    fn test_add_sub() {
+        let values = random_u128s(1, 400);
+        for pair in values.chunks(2) {
+            let (a, b) = (pair[0] >> 1, pair[1] >> 1);
+            assert_eq!(Some(a + b), (big(a) + big(b)).to_u128());
+
+            let (high, low) = (a.max(b), a.min(b));
+            assert_eq!(Some(high - low), (big(high) - big(low)).to_u128());
+            assert_eq!(low == high, big(low).checked_sub(&big(high)).is_some());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "overflow")]
+    fn test_sub_underflow() {
+        let _ = big(1) - big(2);
+    }
+
+    #[test]
+    fn test_mul() {
+        let values = random_u128s(2, 400);
+        for pair in values.chunks(2) {
+            let (a, b) = (pair[0] as u64, pair[1] as u64);
+            assert_eq!(
+                Some(u128::from(a) * u128::from(b)),
+                (big(a.into()) * big(b.into())).to_u128()
+            );
+        }
+    }
+
+    #[test]
+    fn test_divrem() {
+        let values = random_u128s(3, 1000);
+        for pair in values.chunks(2) {
+            let (a, b) = (pair[0], pair[1].max(1));
+            let (q, r) = big(a).divrem(&big(b));
+            assert_eq!((Some(a / b), Some(a % b)), (q.to_u128(), r.to_u128()));
+        }
+    }
+
+    #[test]
+    fn test_divrem_large() {
+        // One of the Hacker's Delight cases, where the first guess at the quotient is one too
+        // big and the divisor has to be added back
+        let u = big(0x7fff_ffff_8000_0000_0000_0000_0000_0000);
+        let v = big(0x8000_0000_0000_0000_0000_0001);
+        let (q, r) = u.divrem(&v);
+        assert_eq!("4294967294", q.to_string());
+        assert_eq!("39614081257132168792477007874", r.to_string());
+
+        // Otherwise that turns up rarely, so check the identity on lots of multi-limb values
+        let mut rng = SplitMix64::new(4);
+        for _ in 0..200 {
+            let (a_len, b_len) = (rng.gen_range(1..64), rng.gen_range(1..32));
+            let a = BigUint::from_bytes_be(&rng.bytes(a_len));
+            let b = BigUint::from_bytes_be(&rng.bytes(b_len)) + BigUint::one();
+            let (q, r) = a.divrem(&b);
+            assert!(r < b);
+            assert_eq!(a, &(&q * &b) + &r);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "divide by zero")]
+    fn test_divide_by_zero() {
+        let _ = big(1) / BigUint::zero();
+    }
+
+    #[test]
+    fn test_modpow() {
+        let mulmod = |a: u128, b: u128, m: u128| a * b % m;
+        let values = random_u128s(5, 300);
+        for triple in values.chunks(3) {
+            let (base, exponent, modulus) = (triple[0], triple[1], (triple[2] as u64).max(1));
+            let modulus = u128::from(modulus);
+
+            let mut expected = 1 % modulus;
+            let mut b = base % modulus;
+            let mut e = exponent;
+            while e > 0 {
+                if e & 1 == 1 {
+                    expected = mulmod(expected, b, modulus);
+                }
+                b = mulmod(b, b, modulus);
+                e >>= 1;
+            }
+
+            let result = big(base).modpow(&big(exponent), &big(modulus));
+            assert_eq!(Some(expected), result.to_u128());
+        }
+    }
+
+    #[test]
+    fn test_modinv() {
+        let gcd = |mut a: u128, mut b: u128| {
+            while b != 0 {
+                (a, b) = (b, a % b);
+            }
+            a
+        };
+        let values = random_u128s(6, 400);
+        for pair in values.chunks(2) {
+            let (a, m) = (pair[0], u128::from((pair[1] as u64).max(2)));
+
+            match big(a).modinv(&big(m)) {
+                Some(inverse) => {
+                    let inverse = inverse.to_u128().unwrap();
+                    assert!(inverse < m);
+                    assert_eq!(1, (a % m) * inverse % m);
+                }
+                None => assert_ne!(1, gcd(a, m)),
+            }
+        }
+
+        assert_eq!(Some(big(2753)), big(17).modinv(&big(3120)));
+        assert_eq!(None, big(6).modinv(&big(9)));
+    }
+
+    #[test]
+    fn test_nth_root() {
+        let values = random_u128s(7, 300);
+        for (i, &a) in values.iter().enumerate() {
+            let n = (i % 5 + 1) as u32;
+            let root = big(a).nth_root(n).to_u128().unwrap();
+
+            assert!(root.pow(n) <= a);
+            assert!((root + 1).checked_pow(n).is_none_or(|above| above > a));
+        }
+
+        let cube = BigUint::from_bytes_be(&[0xab; 40]).pow(3);
+        assert_eq!(BigUint::from_bytes_be(&[0xab; 40]), cube.nth_root(3));
+    }
+
+    #[test]
+    fn test_bytes() {
+        for a in random_u128s(8, 100) {
+            let bytes = a.to_be_bytes();
+            let start = bytes.iter().position(|&b| b != 0).unwrap_or(15);
+
+            assert_eq!(big(a), BigUint::from_bytes_be(&bytes));
+            assert_eq!(bytes[start..].to_vec(), big(a).to_bytes_be());
+        }
+
+        assert_eq!(vec![0], BigUint::zero().to_bytes_be());
+        assert_eq!(BigUint::zero(), BigUint::from_bytes_be(&[]));
+    }
+
+    #[test]
+    fn test_shifts() {
+        for (i, a) in random_u128s(9, 100).into_iter().enumerate() {
+            let shift = i % 100;
+            assert_eq!(Some(a >> shift), (big(a) >> shift).to_u128());
+            assert_eq!(big(a), (big(a) << shift) >> shift);
+        }
+    }
+
+    #[test]
+    fn test_decimal() {
+        for a in random_u128s(10, 100) {
+            assert_eq!(a.to_string(), big(a).to_string());
+            assert_eq!(Ok(big(a)), a.to_string().parse());
+        }
+
+        let big_power = BigUint::from(10u32).pow(40);
+        assert_eq!(format!("1{}", "0".repeat(40)), big_power.to_string());
+        assert_eq!(Err(ParseBigUintError), "12a".parse::<BigUint>());
+    }
+}
@@ -0,0 +1,217 @@
+use crate::{
+    aes::{self, AesError, CtrCounter, BLOCK_SIZE},
+    rng::RngCore,
+    xor,
+};
+
+/// The text the oracles put before the user data.
+pub const PREFIX: &[u8] = b"comment1=cooking%20MCs;userdata=";
+/// The text the oracles put after the user data.
+pub const SUFFIX: &[u8] = b";comment2=%20like%20a%20pound%20of%20bacon";
+
+const ADMIN: &[u8] = b";admin=true;";
+
+/// URL-encode the metacharacters in user data, so that it can't add pairs of its own.
+#[must_use]
+pub fn quote(userdata: &[u8]) -> Vec<u8> {
+    userdata
+        .iter()
+        .flat_map(|&b| match b {
+            b';' => b"%3B".to_vec(),
+            b'=' => b"%3D".to_vec(),
+            b => vec![b],
+        })
+        .collect()
+}
+
+fn wrap(userdata: &[u8]) -> Vec<u8> {
+    [PREFIX, &quote(userdata), SUFFIX].concat()
+}
+
+/// Whether the decrypted cookie contains `;admin=true;`.
+#[must_use]
+pub fn is_admin(plaintext: &[u8]) -> bool {
+    plaintext.windows(ADMIN.len()).any(|w| w == ADMIN)
+}
+
+/// Encrypts quoted user data inside the cookie with AES-CBC under a random key and IV, and
+/// checks decrypted cookies for admin rights.
+pub struct CbcOracle {
+    key: Vec<u8>,
+    iv: Vec<u8>,
+}
+
+impl CbcOracle {
+    pub fn new<R: RngCore>(rng: &mut R) -> Self {
+        Self {
+            key: rng.bytes(BLOCK_SIZE),
+            iv: rng.bytes(BLOCK_SIZE),
+        }
+    }
+
+    #[must_use]
+    pub fn encrypt(&self, userdata: &[u8]) -> Vec<u8> {
+        aes::cbc_encrypt(&self.key, &self.iv, &wrap(userdata)).expect("Key and IV are one block")
+    }
+
+    pub fn is_admin(&self, ciphertext: &[u8]) -> Result<bool, AesError> {
+        aes::cbc_decrypt(&self.key, &self.iv, ciphertext).map(|plaintext| is_admin(&plaintext))
+    }
+}
+
+/// Encrypts quoted user data inside the cookie with AES-CTR under a random key and nonce, and
+/// checks decrypted cookies for admin rights.
+pub struct CtrOracle {
+    key: Vec<u8>,
+    counter: CtrCounter,
+}
+
+impl CtrOracle {
+    pub fn new<R: RngCore>(rng: &mut R) -> Self {
+        Self {
+            key: rng.bytes(BLOCK_SIZE),
+            counter: CtrCounter::LittleEndian {
+                nonce: rng.next_u64(),
+            },
+        }
+    }
+
+    #[must_use]
+    pub fn encrypt(&self, userdata: &[u8]) -> Vec<u8> {
+        aes::ctr(&self.key, self.counter, &wrap(userdata)).expect("Key is one block long")
+    }
+
+    pub fn is_admin(&self, ciphertext: &[u8]) -> Result<bool, AesError> {
+        aes::ctr(&self.key, self.counter, ciphertext).map(|plaintext| is_admin(&plaintext))
+    }
+}
+
+/// Change the plaintext at the start of a CBC block from `known` to `desired`. Each
+/// plaintext block is XORed with the previous ciphertext block after decryption, so XORing
+/// that ciphertext block with `known ^ desired` flips exactly those bits (and scrambles the
+/// previous block's plaintext).
+///
+/// Returns `None` if `known` and `desired` differ in length or are longer than a block, or if
+/// the block has no previous block in the ciphertext.
+#[must_use]
+pub fn cbc_flip(ciphertext: &[u8], block: usize, known: &[u8], desired: &[u8]) -> Option<Vec<u8>> {
+    let delta = xor::fixed(known, desired)?;
+    if delta.len() > BLOCK_SIZE
+        || block == 0
+        || block.checked_add(1)?.checked_mul(BLOCK_SIZE)? > ciphertext.len()
+    {
+        return None;
+    }
+
+    let start = (block - 1) * BLOCK_SIZE;
+    let mut flipped = ciphertext.to_vec();
+    let previous = &mut flipped[start..start + delta.len()];
+    previous.copy_from_slice(&xor::fixed(previous, &delta)?);
+
+    Some(flipped)
+}
+
+/// Forge a cookie with `;admin=true;` through a CBC oracle. We send a sacrificial block
+/// followed by a block of known filler, then flip the filler into the admin text.
+pub fn forge_admin_cbc<F>(encrypt: F) -> Vec<u8>
+where
+    F: Fn(&[u8]) -> Vec<u8>,
+{
+    let align = (BLOCK_SIZE - PREFIX.len() % BLOCK_SIZE) % BLOCK_SIZE;
+    let target = (PREFIX.len() + align) / BLOCK_SIZE + 1;
+
+    let known = [b'A'; BLOCK_SIZE];
+    let desired = [&known[ADMIN.len()..], ADMIN].concat();
+
+    let ciphertext = encrypt(&vec![b'A'; align + 2 * BLOCK_SIZE]);
+
+    cbc_flip(&ciphertext, target, &known, &desired).expect("The target block follows another")
+}
+
+/// Change the plaintext at `offset` of a CTR ciphertext from `known` to `desired`. The
+/// plaintext is the ciphertext XORed with the keystream, so XORing the ciphertext bytes with
+/// `known ^ desired` flips exactly those bits and nothing else.
+///
+/// Returns `None` if `known` and `desired` differ in length or run past the end of the
+/// ciphertext.
+#[must_use]
+pub fn ctr_flip(ciphertext: &[u8], offset: usize, known: &[u8], desired: &[u8]) -> Option<Vec<u8>> {
+    let delta = xor::fixed(known, desired)?;
+    let range = offset..offset.checked_add(delta.len())?;
+
+    let mut flipped = ciphertext.to_vec();
+    let target = flipped.get_mut(range)?;
+    target.copy_from_slice(&xor::fixed(target, &delta)?);
+
+    Some(flipped)
+}
+
+/// Forge a cookie with `;admin=true;` through a CTR oracle. We send filler as long as the
+/// admin text and flip it in place. Unlike CBC, nothing else gets scrambled.
+pub fn forge_admin_ctr<F>(encrypt: F) -> Vec<u8>
+where
+    F: Fn(&[u8]) -> Vec<u8>,
+{
+    let known = vec![b'A'; ADMIN.len()];
+
+    let ciphertext = encrypt(&known);
+
+    ctr_flip(&ciphertext, PREFIX.len(), &known, ADMIN).expect("The user data is in range")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(b"a%3Badmin%3Dtrue%3B".to_vec(), quote(b"a;admin=true;"));
+    }
+
+    #[test]
+    fn test_oracle_quotes_user_data() {
+        let oracle = CbcOracle::new(&mut SplitMix64::new(16));
+
+        assert_eq!(Ok(false), oracle.is_admin(&oracle.encrypt(b";admin=true;")));
+    }
+
+    #[test]
+    fn test_cbc_flip() {
+        let key = b"YELLOW SUBMARINE";
+        let iv = [0; BLOCK_SIZE];
+        let ciphertext = aes::cbc_encrypt(key, &iv, b"0123456789abcdefhello world").unwrap();
+
+        let flipped = cbc_flip(&ciphertext, 1, b"hello", b"jello").unwrap();
+        let plaintext = aes::cbc_decrypt(key, &iv, &flipped).unwrap();
+
+        assert_eq!(b"jello world", &plaintext[BLOCK_SIZE..]);
+    }
+
+    #[test]
+    fn test_cbc_flip_rejects_bad_input() {
+        let ciphertext = [0; 2 * BLOCK_SIZE];
+
+        assert_eq!(None, cbc_flip(&ciphertext, 0, b"a", b"b"));
+        assert_eq!(None, cbc_flip(&ciphertext, 2, b"a", b"b"));
+        assert_eq!(None, cbc_flip(&ciphertext, 1, b"a", b"bc"));
+        assert_eq!(None, cbc_flip(&ciphertext, usize::MAX, b"a", b"b"));
+        assert_eq!(None, cbc_flip(&ciphertext, usize::MAX / 16, b"a", b"b"));
+    }
+
+    #[test]
+    fn test_ctr_flip() {
+        let key = b"YELLOW SUBMARINE";
+        let counter = CtrCounter::LittleEndian { nonce: 0 };
+        let ciphertext = aes::ctr(key, counter, b"hello world").unwrap();
+
+        let flipped = ctr_flip(&ciphertext, 6, b"world", b"there").unwrap();
+
+        assert_eq!(
+            b"hello there".to_vec(),
+            aes::ctr(key, counter, &flipped).unwrap()
+        );
+        assert_eq!(None, ctr_flip(&ciphertext, 7, b"world", b"there"));
+        assert_eq!(None, ctr_flip(&ciphertext, usize::MAX, b"a", b"b"));
+    }
+}
@@ -0,0 +1,260 @@
+use std::{cell::Cell, fmt};
+
+use crate::{
+    aes::{self, BLOCK_SIZE},
+    rng::RngCore,
+};
+
+/// The largest block size we look for.
+const MAX_BLOCK_SIZE: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ByteAtATimeError {
+    /// The ciphertext length never changed while growing the input.
+    BlockSizeNotFound,
+    /// Identical input blocks didn't produce identical ciphertext blocks.
+    NotEcb,
+    /// No amount of padding lined our input up with a block boundary.
+    PrefixNotFound,
+    /// The ciphertext never got longer while filling out the suffix's last block.
+    SuffixLengthNotFound,
+    /// None of the 256 candidates matched the ciphertext at this offset of the suffix.
+    ByteNotFound(usize),
+}
+
+impl fmt::Display for ByteAtATimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BlockSizeNotFound => write!(f, "could not discover the block size"),
+            Self::NotEcb => write!(f, "the oracle does not encrypt with ECB"),
+            Self::PrefixNotFound => write!(f, "could not discover the prefix length"),
+            Self::SuffixLengthNotFound => write!(f, "could not discover the suffix length"),
+            Self::ByteNotFound(i) => write!(f, "no candidate matched suffix byte {i}"),
+        }
+    }
+}
+
+impl std::error::Error for ByteAtATimeError {}
+
+/// An ECB oracle which encrypts `prefix || input || suffix` under a random key. The prefix and
+/// suffix stay the same for every call.
+pub struct EcbOracle {
+    key: Vec<u8>,
+    prefix: Vec<u8>,
+    suffix: Vec<u8>,
+}
+
+impl EcbOracle {
+    pub fn new<R: RngCore>(rng: &mut R, prefix: Vec<u8>, suffix: Vec<u8>) -> Self {
+        Self {
+            key: rng.bytes(BLOCK_SIZE),
+            prefix,
+            suffix,
+        }
+    }
+
+    /// An oracle with a random-length (0 to 63 bytes) random prefix, as in challenge 14.
+    pub fn with_random_prefix<R: RngCore>(rng: &mut R, suffix: Vec<u8>) -> Self {
+        let prefix_len = rng.gen_range(0..64);
+        let prefix = rng.bytes(prefix_len);
+
+        Self::new(rng, prefix, suffix)
+    }
+
+    #[must_use]
+    pub fn encrypt(&self, input: &[u8]) -> Vec<u8> {
+        let plaintext = [self.prefix.as_slice(), input, &self.suffix].concat();
+
+        aes::ecb_encrypt_padded(&self.key, &plaintext).expect("Key is one block long")
+    }
+}
+
+/// What the attack learned about the oracle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recovered {
+    pub block_size: usize,
+    pub prefix_len: usize,
+    pub suffix: Vec<u8>,
+    /// How many times the oracle was called.
+    pub queries: usize,
+}
+
+/// Wraps an oracle to count how many times it's called.
+struct Counted<F> {
+    oracle: F,
+    queries: Cell<usize>,
+}
+
+impl<F: Fn(&[u8]) -> Vec<u8>> Counted<F> {
+    fn call(&self, input: &[u8]) -> Vec<u8> {
+        self.queries.set(self.queries.get() + 1);
+        (self.oracle)(input)
+    }
+}
+
+/// Find the block size by growing the input until the ciphertext gets longer. The length
+/// always grows by exactly one block.
+fn find_block_size<F: Fn(&[u8]) -> Vec<u8>>(
+    oracle: &Counted<F>,
+) -> Result<usize, ByteAtATimeError> {
+    let initial = oracle.call(&[]).len();
+
+    (1..=MAX_BLOCK_SIZE)
+        .map(|len| oracle.call(&vec![b'A'; len]).len())
+        .find(|&len| len > initial)
+        .map(|len| len - initial)
+        .ok_or(ByteAtATimeError::BlockSizeNotFound)
+}
+
+/// Where two identical blocks of our input sit next to each other in the ciphertext, if they
+/// do. Blocks the prefix or suffix repeat on their own don't count, so the input is sent twice,
+/// with two different fillers, and only a duplicate which changes with the filler is ours.
+fn find_input_duplicates<F: Fn(&[u8]) -> Vec<u8>>(
+    oracle: &Counted<F>,
+    len: usize,
+    block_size: usize,
+) -> Option<usize> {
+    let [a, b] = [b'A', b'B'].map(|filler| oracle.call(&vec![filler; len]));
+    let blocks: Vec<_> = a
+        .chunks_exact(block_size)
+        .zip(b.chunks_exact(block_size))
+        .collect();
+
+    blocks
+        .windows(2)
+        .position(|pair| pair[0] == pair[1] && pair[0].0 != pair[0].1)
+}
+
+/// Find the length of the prefix by padding our input until two identical blocks of it line up
+/// with block boundaries. The bytes around our input can't pass for both fillers, so the first
+/// padding that lines up is the right one.
+fn find_prefix_len<F: Fn(&[u8]) -> Vec<u8>>(
+    oracle: &Counted<F>,
+    block_size: usize,
+) -> Result<usize, ByteAtATimeError> {
+    (0..block_size)
+        .find_map(|pad| {
+            let i = find_input_duplicates(oracle, pad + 2 * block_size, block_size)?;
+            (i * block_size).checked_sub(pad)
+        })
+        .ok_or(ByteAtATimeError::PrefixNotFound)
+}
+
+/// Recover the secret suffix an ECB oracle appends to our input, one byte at a time.
+///
+/// We line the next unknown byte up as the last byte of a block whose other bytes we know,
+/// then try every possible last byte until one encrypts to the same block. Any fixed prefix
+/// the oracle adds is first measured and padded out to a block boundary.
+pub fn recover_suffix<F>(oracle: F) -> Result<Recovered, ByteAtATimeError>
+where
+    F: Fn(&[u8]) -> Vec<u8>,
+{
+    let oracle = Counted {
+        oracle,
+        queries: Cell::new(0),
+    };
+
+    let block_size = find_block_size(&oracle)?;
+
+    if find_input_duplicates(&oracle, 3 * block_size, block_size).is_none() {
+        return Err(ByteAtATimeError::NotEcb);
+    }
+
+    let prefix_len = find_prefix_len(&oracle, block_size)?;
+    let align = vec![b'A'; (block_size - prefix_len % block_size) % block_size];
+    let start = prefix_len + align.len();
+
+    // The ciphertext gets a block longer as soon as the plaintext fills its last block.
+    let base_len = oracle.call(&align).len();
+    let suffix_len = (1..=block_size)
+        .find_map(|extra| {
+            let input = [align.as_slice(), &vec![b'A'; extra]].concat();
+            (oracle.call(&input).len() > base_len).then(|| base_len - start - extra)
+        })
+        .ok_or(ByteAtATimeError::SuffixLengthNotFound)?;
+
+    let mut known = vec![b'A'; block_size - 1];
+    for i in 0..suffix_len {
+        let filler = vec![b'A'; block_size - 1 - i % block_size];
+        let offset = start + (i / block_size) * block_size;
+
+        let ciphertext = oracle.call(&[align.as_slice(), &filler].concat());
+        let target = &ciphertext[offset..offset + block_size];
+
+        let window = &known[known.len() - (block_size - 1)..];
+        let byte = (0x00..=0xFF)
+            .find(|&b| {
+                let input = [align.as_slice(), window, &[b]].concat();
+                &oracle.call(&input)[start..start + block_size] == target
+            })
+            .ok_or(ByteAtATimeError::ByteNotFound(i))?;
+
+        known.push(byte);
+    }
+
+    Ok(Recovered {
+        block_size,
+        prefix_len,
+        suffix: known.split_off(block_size - 1),
+        queries: oracle.queries.get(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    #[test]
+    fn test_recovers_suffix_for_every_prefix_length() {
+        let mut rng = SplitMix64::new(3);
+        let suffix = b"A secret suffix which spans a few blocks".to_vec();
+
+        for prefix_len in 0..=2 * BLOCK_SIZE {
+            let prefix = rng.bytes(prefix_len);
+            let oracle = EcbOracle::new(&mut rng, prefix, suffix.clone());
+
+            let recovered = recover_suffix(|input| oracle.encrypt(input)).unwrap();
+
+            assert_eq!(prefix_len, recovered.prefix_len);
+            assert_eq!(suffix, recovered.suffix);
+        }
+    }
+
+    #[test]
+    fn test_input_surrounded_by_filler() {
+        let mut rng = SplitMix64::new(3);
+        let oracle = EcbOracle::new(&mut rng, b"prefix AA".to_vec(), b"AAsuffix".to_vec());
+
+        let recovered = recover_suffix(|input| oracle.encrypt(input)).unwrap();
+
+        assert_eq!(9, recovered.prefix_len);
+        assert_eq!(b"AAsuffix".to_vec(), recovered.suffix);
+    }
+
+    #[test]
+    fn test_prefix_with_repeated_blocks() {
+        let mut rng = SplitMix64::new(3);
+
+        for prefix in [
+            vec![0; 2 * BLOCK_SIZE],
+            [vec![b'A'; 2 * BLOCK_SIZE], vec![1; 5]].concat(),
+        ] {
+            let oracle = EcbOracle::new(&mut rng, prefix.clone(), b"secret suffix".to_vec());
+
+            let recovered = recover_suffix(|input| oracle.encrypt(input)).unwrap();
+
+            assert_eq!(prefix.len(), recovered.prefix_len);
+            assert_eq!(b"secret suffix".to_vec(), recovered.suffix);
+        }
+    }
+
+    #[test]
+    fn test_rejects_cbc() {
+        let result = recover_suffix(|input| {
+            aes::cbc_encrypt(b"YELLOW SUBMARINE", &[0; BLOCK_SIZE], input).unwrap()
+        });
+
+        assert_eq!(Err(ByteAtATimeError::NotEcb), result);
+    }
+}
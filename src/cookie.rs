@@ -0,0 +1,168 @@
+use crate::{
+    aes::{self, AesError, BLOCK_SIZE},
+    padding::pkcs7_pad,
+    rng::RngCore,
+};
+
+/// A `foo=bar&baz=qux` structured cookie. The pairs keep the order they were parsed or
+/// inserted in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cookie {
+    pairs: Vec<(String, String)>,
+}
+
+impl Cookie {
+    /// Parse a cookie. A pair without `=` gets an empty value, and empty pairs are skipped.
+    #[must_use]
+    pub fn parse(s: &str) -> Self {
+        let pairs = s
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (key.to_string(), value.to_string())
+            })
+            .collect();
+
+        Self { pairs }
+    }
+
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find_map(|(k, v)| (k == key).then_some(v.as_str()))
+    }
+
+    pub fn push(&mut self, key: &str, value: &str) {
+        self.pairs.push((key.to_string(), value.to_string()));
+    }
+
+    #[must_use]
+    pub fn encode(&self) -> String {
+        self.pairs
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
+
+/// The cookie for a user profile. Metacharacters are stripped from the email so that it can't
+/// add pairs of its own.
+#[must_use]
+pub fn profile_for(email: &str) -> Cookie {
+    let email: String = email.chars().filter(|c| !matches!(c, '&' | '=')).collect();
+
+    let mut cookie = Cookie::default();
+    cookie.push("email", &email);
+    cookie.push("uid", "10");
+    cookie.push("role", "user");
+
+    cookie
+}
+
+/// Hands out ECB-encrypted profiles under a random key, and reads them back.
+pub struct ProfileOracle {
+    key: Vec<u8>,
+}
+
+impl ProfileOracle {
+    pub fn new<R: RngCore>(rng: &mut R) -> Self {
+        Self {
+            key: rng.bytes(BLOCK_SIZE),
+        }
+    }
+
+    #[must_use]
+    pub fn encrypt(&self, email: &str) -> Vec<u8> {
+        aes::ecb_encrypt_padded(&self.key, profile_for(email).encode().as_bytes())
+            .expect("Key is one block long")
+    }
+
+    pub fn decrypt(&self, ciphertext: &[u8]) -> Result<Cookie, AesError> {
+        let plaintext = aes::ecb_decrypt_padded(&self.key, ciphertext)?;
+
+        Ok(Cookie::parse(&String::from_utf8_lossy(&plaintext)))
+    }
+}
+
+fn padding_to_block(len: usize) -> usize {
+    (BLOCK_SIZE - len % BLOCK_SIZE) % BLOCK_SIZE
+}
+
+/// Forge the ciphertext of a profile with `role=admin`, using nothing but the encryption
+/// oracle. The offsets come from the (public) layout of `profile_for`.
+///
+/// First we pad an email so that `admin` and its PKCS#7 padding land in a block of their own,
+/// and keep that ciphertext block. Then we pick an email length which leaves the role's value
+/// at the start of the last block, and replace that block with the one we kept.
+pub fn forge_admin<F>(encrypt: F) -> Vec<u8>
+where
+    F: Fn(&str) -> Vec<u8>,
+{
+    let template = profile_for("").encode();
+    let email_offset = template.find('&').expect("Profiles have several pairs");
+    let role_offset = template.find("role=").expect("Profiles have a role") + "role=".len();
+
+    let admin_offset = email_offset + padding_to_block(email_offset);
+    let admin_email = String::from_utf8(
+        [
+            vec![b'A'; admin_offset - email_offset],
+            pkcs7_pad(b"admin", BLOCK_SIZE),
+        ]
+        .concat(),
+    )
+    .expect("Padding bytes are valid ASCII");
+    let admin_block = &encrypt(&admin_email)[admin_offset..admin_offset + BLOCK_SIZE];
+
+    let user_email = "a".repeat(padding_to_block(role_offset));
+    let user_ciphertext = encrypt(&user_email);
+
+    [
+        &user_ciphertext[..role_offset + user_email.len()],
+        admin_block,
+    ]
+    .concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let cookie = Cookie::parse("foo=bar&baz=qux&zap=zazzle");
+
+        assert_eq!(Some("bar"), cookie.get("foo"));
+        assert_eq!(Some("qux"), cookie.get("baz"));
+        assert_eq!(Some("zazzle"), cookie.get("zap"));
+        assert_eq!(None, cookie.get("zip"));
+        assert_eq!("foo=bar&baz=qux&zap=zazzle", cookie.encode());
+    }
+
+    #[test]
+    fn test_parse_missing_value() {
+        let cookie = Cookie::parse("foo&bar=&&baz=a=b");
+
+        assert_eq!(Some(""), cookie.get("foo"));
+        assert_eq!(Some(""), cookie.get("bar"));
+        assert_eq!(Some("a=b"), cookie.get("baz"));
+    }
+
+    #[test]
+    fn test_profile_for() {
+        assert_eq!(
+            "email=foo@bar.com&uid=10&role=user",
+            profile_for("foo@bar.com").encode()
+        );
+    }
+
+    #[test]
+    fn test_profile_for_strips_metacharacters() {
+        assert_eq!(
+            "email=foo@bar.comroleadmin&uid=10&role=user",
+            profile_for("foo@bar.com&role=admin").encode()
+        );
+    }
+}
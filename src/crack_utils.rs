@@ -103,7 +103,7 @@ pub fn guess_keysizes(input: &[u8], min: usize, max: usize) -> Vec<usize> {
         })
         .collect();
 
-    keysizes.sort_by(|(n1, _), (n2, _)| n1.cmp(n2));
+    keysizes.sort_by_key(|(n, _)| *n);
 
     keysizes.into_iter().take(3).map(|(_, k)| k).collect()
 }
@@ -0,0 +1,231 @@
+use crate::{score, xor};
+
+/// The outcome of placing a crib at one offset of a ciphertext.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drag {
+    pub offset: usize,
+    /// The keystream the crib implies at this offset.
+    pub keystream: Vec<u8>,
+    /// What that keystream decrypts every ciphertext to at this offset, or `None` for the
+    /// ciphertexts which don't reach that far.
+    pub plaintexts: Vec<Option<Vec<u8>>>,
+    /// The average score of the other ciphertexts' implied plaintexts (lower is better), or
+    /// `None` if any of them isn't printable or none of them reach this offset.
+    pub score: Option<usize>,
+}
+
+/// A crib-dragging session over ciphertexts which were all encrypted with the same keystream,
+/// for when there are too few or too short ciphertexts for statistics to work.
+///
+/// Guess a word one of the plaintexts might contain and `drag` it across that ciphertext to
+/// see what it implies for the others. Once a guess looks right, `pin` the keystream it
+/// implies and see every decryption fill in.
+#[derive(Debug, Clone)]
+pub struct CribSession {
+    ciphertexts: Vec<Vec<u8>>,
+    keystream: Vec<Option<u8>>,
+}
+
+impl CribSession {
+    #[must_use]
+    pub fn new(ciphertexts: Vec<Vec<u8>>) -> Self {
+        let len = ciphertexts.iter().map(Vec::len).max().unwrap_or(0);
+
+        Self {
+            ciphertexts,
+            keystream: vec![None; len],
+        }
+    }
+
+    /// Try the crib at every offset of one ciphertext, best ranked first.
+    ///
+    /// # Panics
+    ///
+    /// If there's no ciphertext at `index`.
+    #[must_use]
+    pub fn drag(&self, index: usize, crib: &[u8]) -> Vec<Drag> {
+        let ciphertext = &self.ciphertexts[index];
+        if crib.is_empty() || crib.len() > ciphertext.len() {
+            return Vec::new();
+        }
+
+        let mut drags: Vec<_> = (0..=ciphertext.len() - crib.len())
+            .map(|offset| {
+                let range = offset..offset + crib.len();
+                let keystream = xor::fixed(&ciphertext[range.clone()], crib)
+                    .expect("Crib and ciphertext slice have the same length");
+
+                let plaintexts: Vec<_> = self
+                    .ciphertexts
+                    .iter()
+                    .map(|c| c.get(range.clone()).and_then(|c| xor::fixed(c, &keystream)))
+                    .collect();
+
+                let others: Vec<_> = plaintexts
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i != index)
+                    .filter_map(|(_, p)| p.as_deref())
+                    .collect();
+                let score = others
+                    .iter()
+                    .map(|p| score::score(p))
+                    .sum::<Option<usize>>()
+                    .filter(|_| !others.is_empty())
+                    .map(|total| total / others.len());
+
+                Drag {
+                    offset,
+                    keystream,
+                    plaintexts,
+                    score,
+                }
+            })
+            .collect();
+
+        // `None` sorts before `Some`, so rank unscored drags last
+        drags.sort_by_key(|drag| (drag.score.is_none(), drag.score));
+
+        drags
+    }
+
+    /// Fix the keystream bytes starting at `offset`. Bytes past the end of the longest
+    /// ciphertext are ignored.
+    pub fn pin(&mut self, offset: usize, keystream: &[u8]) {
+        for (slot, &b) in self.keystream.iter_mut().skip(offset).zip(keystream) {
+            *slot = Some(b);
+        }
+    }
+
+    /// Fix the keystream which decrypts one ciphertext to the given plaintext at `offset`.
+    ///
+    /// # Panics
+    ///
+    /// If there's no ciphertext at `index`.
+    pub fn pin_plaintext(&mut self, index: usize, offset: usize, plaintext: &[u8]) {
+        let ciphertext = self.ciphertexts[index].iter().skip(offset);
+        let keystream: Vec<_> = ciphertext.zip(plaintext).map(|(c, p)| c ^ p).collect();
+
+        self.pin(offset, &keystream);
+    }
+
+    /// Forget the keystream bytes in the given range.
+    pub fn unpin(&mut self, offset: usize, len: usize) {
+        for slot in self.keystream.iter_mut().skip(offset).take(len) {
+            *slot = None;
+        }
+    }
+
+    #[must_use]
+    pub fn keystream(&self) -> &[Option<u8>] {
+        &self.keystream
+    }
+
+    /// Every ciphertext decrypted with the pinned keystream, with `None` where the keystream
+    /// isn't known yet.
+    #[must_use]
+    pub fn decryptions(&self) -> Vec<Vec<Option<u8>>> {
+        self.ciphertexts
+            .iter()
+            .map(|c| {
+                c.iter()
+                    .zip(&self.keystream)
+                    .map(|(c, k)| k.map(|k| c ^ k))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The decryptions as text, with `placeholder` standing in for unknown bytes.
+    #[must_use]
+    pub fn render(&self, placeholder: char) -> Vec<String> {
+        self.decryptions()
+            .into_iter()
+            .map(|d| {
+                d.into_iter()
+                    .map(|b| b.map_or(placeholder, char::from))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAINTEXTS: [&[u8]; 3] = [
+        b"attack the castle at dawn",
+        b"the bridge is guarded",
+        b"meet me by the river",
+    ];
+
+    fn session() -> (CribSession, Vec<u8>) {
+        let keystream: Vec<u8> = (0..32u8)
+            .map(|i| i.wrapping_mul(73).wrapping_add(29))
+            .collect();
+        let ciphertexts = PLAINTEXTS
+            .iter()
+            .map(|p| xor::fixed(p, &keystream[..p.len()]).unwrap())
+            .collect();
+
+        (CribSession::new(ciphertexts), keystream)
+    }
+
+    #[test]
+    fn test_drag_ranks_the_right_offset_first() {
+        let (session, keystream) = session();
+
+        let drags = session.drag(1, b"the ");
+
+        assert_eq!(18, drags.len());
+        assert_eq!(0, drags[0].offset);
+        assert_eq!(keystream[..4], drags[0].keystream);
+        assert_eq!(Some(b"atta".to_vec()), drags[0].plaintexts[0]);
+        assert_eq!(Some(b"meet".to_vec()), drags[0].plaintexts[2]);
+    }
+
+    #[test]
+    fn test_drag_reports_short_ciphertexts() {
+        let (session, _) = session();
+
+        let drag = session
+            .drag(0, b" at ")
+            .into_iter()
+            .find(|d| d.offset == 17)
+            .unwrap();
+
+        assert_eq!(Some(b"rded".to_vec()), drag.plaintexts[1]);
+        assert_eq!(None, drag.plaintexts[2]);
+    }
+
+    #[test]
+    fn test_drag_with_oversized_crib() {
+        let (session, _) = session();
+
+        assert!(session.drag(2, &[b'a'; 21]).is_empty());
+    }
+
+    #[test]
+    fn test_pin_and_unpin() {
+        let (mut session, keystream) = session();
+
+        session.pin_plaintext(0, 0, b"attack the");
+        session.pin(10, &keystream[10..12]);
+
+        assert_eq!(
+            vec![
+                "attack the c_____________",
+                "the bridge i_________",
+                "meet me by t________",
+            ],
+            session.render('_')
+        );
+
+        session.unpin(4, 8);
+
+        assert_eq!("meet________", &session.render('_')[2][..12]);
+        assert_eq!(None, session.keystream()[4]);
+        assert_eq!(Some(keystream[3]), session.keystream()[3]);
+    }
+}
@@ -0,0 +1,67 @@
+use crate::{
+    aes::{self, AesError, CtrCounter, BLOCK_SIZE},
+    rng::RngCore,
+    xor,
+};
+
+/// A disk-encryption style service which encrypts with AES-CTR under a random key and nonce,
+/// and lets callers edit ciphertexts in place without ever revealing the key.
+pub struct EditOracle {
+    key: Vec<u8>,
+    counter: CtrCounter,
+}
+
+impl EditOracle {
+    pub fn new<R: RngCore>(rng: &mut R) -> Self {
+        Self {
+            key: rng.bytes(BLOCK_SIZE),
+            counter: CtrCounter::LittleEndian {
+                nonce: rng.next_u64(),
+            },
+        }
+    }
+
+    #[must_use]
+    pub fn encrypt(&self, plaintext: &[u8]) -> Vec<u8> {
+        aes::ctr(&self.key, self.counter, plaintext).expect("Key is one block long")
+    }
+
+    /// Replace the plaintext at `offset` of the ciphertext with `newtext`.
+    pub fn edit(
+        &self,
+        ciphertext: &[u8],
+        offset: usize,
+        newtext: &[u8],
+    ) -> Result<Vec<u8>, AesError> {
+        aes::ctr_edit(&self.key, self.counter, ciphertext, offset, newtext)
+    }
+}
+
+/// Recover the plaintext of a CTR ciphertext through an edit function. Editing the whole
+/// ciphertext to zeros hands us the keystream itself, since XORing with zero changes nothing.
+pub fn recover_plaintext<F>(ciphertext: &[u8], edit: F) -> Vec<u8>
+where
+    F: Fn(&[u8], usize, &[u8]) -> Vec<u8>,
+{
+    let keystream = edit(ciphertext, 0, &vec![0; ciphertext.len()]);
+
+    xor::fixed(ciphertext, &keystream).expect("Editing in place keeps the length")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    #[test]
+    fn test_recover_plaintext() {
+        let oracle = EditOracle::new(&mut SplitMix64::new(25));
+        let ciphertext = oracle.encrypt(b"Attack at dawn, or maybe a bit later");
+
+        let plaintext = recover_plaintext(&ciphertext, |c, offset, newtext| {
+            oracle.edit(c, offset, newtext).unwrap()
+        });
+
+        assert_eq!(b"Attack at dawn, or maybe a bit later".to_vec(), plaintext);
+    }
+}
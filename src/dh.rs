@@ -0,0 +1,171 @@
+use crate::{bigint::BigUint, hex, rng::RngCore, sha1};
+
+/// The 1536-bit MODP prime from RFC 3526, the one challenge 33 uses.
+const MODP_1536_PRIME: &str = concat!(
+    "ffffffffffffffffc90fdaa22168c234c4c6628b80dc1cd129024e088a67cc74",
+    "020bbea63b139b22514a08798e3404ddef9519b3cd3a431b302b0a6df25f1437",
+    "4fe1356d6d51c245e485b576625e7ec6f44c42e9a637ed6b0bff5cb6f406b7ed",
+    "ee386bfb5a899fa5ae9f24117c4b1fe649286651ece45b3dc2007cb8a163bf05",
+    "98da48361c55d39a69163fa8fd24cf5f83655d23dca3ad961c62f356208552bb",
+    "9ed529077096966d670c354e4abc9804f1746c08ca237327ffffffffffffffff",
+);
+
+/// The size of the AES keys derived from a shared secret, in bytes.
+pub const KEY_SIZE: usize = 16;
+
+/// The public parameters of a Diffie-Hellman exchange: a prime modulus `p` and a generator
+/// `g`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    p: BigUint,
+    g: BigUint,
+}
+
+/// One side's keys. The private key stays put; the public key is what gets sent across.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyPair {
+    pub private: BigUint,
+    pub public: BigUint,
+}
+
+impl Group {
+    /// A group with any parameters, such as tiny ones for quick tests.
+    ///
+    /// # Panics
+    ///
+    /// If `p` is too small to leave any private keys to pick from.
+    #[must_use]
+    pub fn new(p: BigUint, g: BigUint) -> Self {
+        assert!(p > BigUint::from(2u32), "Modulus must be greater than 2");
+
+        Self { p, g }
+    }
+
+    /// The 1536-bit MODP group from RFC 3526, with generator 2.
+    #[must_use]
+    pub fn modp_1536() -> Self {
+        Self::new(
+            BigUint::from_bytes_be(&hex::decode(MODP_1536_PRIME)),
+            BigUint::from(2u32),
+        )
+    }
+
+    #[must_use]
+    pub fn p(&self) -> &BigUint {
+        &self.p
+    }
+
+    #[must_use]
+    pub fn g(&self) -> &BigUint {
+        &self.g
+    }
+
+    /// A uniformly random private key from 1 to `p - 2`, and the public key `g^a mod p` that
+    /// goes with it.
+    pub fn key_pair<R: RngCore>(&self, rng: &mut R) -> KeyPair {
+        let private = random_below(rng, &(&self.p - &BigUint::from(2u32))) + BigUint::one();
+        let public = self.public_key(&private);
+
+        KeyPair { private, public }
+    }
+
+    #[must_use]
+    pub fn public_key(&self, private: &BigUint) -> BigUint {
+        self.g.modpow(private, &self.p)
+    }
+
+    /// The secret both sides arrive at: `B^a mod p`, which is the same as `A^b mod p`.
+    #[must_use]
+    pub fn shared_secret(&self, private: &BigUint, their_public: &BigUint) -> BigUint {
+        their_public.modpow(private, &self.p)
+    }
+}
+
+/// An AES-128 key made from a shared secret: the first 16 bytes of the SHA-1 of its big-endian
+/// bytes.
+#[must_use]
+pub fn derive_key(shared: &BigUint) -> [u8; KEY_SIZE] {
+    let mut key = [0; KEY_SIZE];
+    key.copy_from_slice(&sha1::sha1(&shared.to_bytes_be())[..KEY_SIZE]);
+    key
+}
+
+/// A uniformly random number below `bound`, by drawing as many bits as `bound` has until one
+/// comes out small enough (which takes fewer than two draws on average).
+fn random_below<R: RngCore>(rng: &mut R, bound: &BigUint) -> BigUint {
+    let bits = bound.bits();
+    let mut bytes = vec![0; bits.div_ceil(8)];
+
+    loop {
+        rng.fill_bytes(&mut bytes);
+        if bits % 8 != 0 {
+            bytes[0] &= (1 << (bits % 8)) - 1;
+        }
+        let candidate = BigUint::from_bytes_be(&bytes);
+        if &candidate < bound {
+            return candidate;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    #[test]
+    fn test_toy_exchange() {
+        let group = Group::new(BigUint::from(37u32), BigUint::from(5u32));
+        let mut rng = SplitMix64::new(1);
+
+        for _ in 0..100 {
+            let alice = group.key_pair(&mut rng);
+            let bob = group.key_pair(&mut rng);
+
+            assert!(alice.private >= BigUint::one());
+            assert!(alice.private < BigUint::from(36u32));
+            assert_eq!(
+                BigUint::from(5u128.pow(alice.private.to_u128().unwrap() as u32) % 37),
+                alice.public
+            );
+            assert_eq!(
+                group.shared_secret(&alice.private, &bob.public),
+                group.shared_secret(&bob.private, &alice.public)
+            );
+        }
+    }
+
+    #[test]
+    fn test_modp_1536() {
+        let group = Group::modp_1536();
+
+        assert_eq!(1536, group.p().bits());
+        assert_eq!(BigUint::from(2u32), *group.g());
+        // p is prime, so Fermat's little theorem holds
+        assert_eq!(
+            BigUint::one(),
+            group.g().modpow(&(group.p() - &BigUint::one()), group.p())
+        );
+    }
+
+    #[test]
+    fn test_random_below_covers_range() {
+        let mut rng = SplitMix64::new(2);
+        let bound = BigUint::from(10u32);
+        let mut seen = [false; 10];
+
+        for _ in 0..200 {
+            let n = random_below(&mut rng, &bound).to_u128().unwrap() as usize;
+            seen[n] = true;
+        }
+
+        assert!(seen.iter().all(|&s| s));
+    }
+
+    #[test]
+    fn test_derive_key() {
+        let shared = BigUint::from(0x0102u32);
+
+        assert_eq!(sha1::sha1(&[1, 2])[..KEY_SIZE], derive_key(&shared));
+    }
+}
@@ -0,0 +1,198 @@
+use std::cmp::Reverse;
+
+use crate::{crack_utils::transpose, xor};
+
+/// How to deal with ciphertexts of different lengths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lengths {
+    /// Cut every ciphertext down to the shortest one, and break the result like repeating-key
+    /// XOR with a key as long as the shortest ciphertext.
+    Truncate,
+    /// Break every column up to the longest ciphertext, using whichever ciphertexts reach it.
+    Ragged,
+}
+
+/// One byte of recovered keystream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeystreamByte {
+    pub byte: u8,
+    /// The score of the column decrypted with this byte (lower is better), or `None` if no byte
+    /// decrypted the column to printable text, in which case the byte is a placeholder.
+    pub score: Option<usize>,
+    /// How many ciphertexts reached this column.
+    pub samples: usize,
+}
+
+/// A keystream shared by many ciphertexts, recovered column by column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecoveredKeystream {
+    pub columns: Vec<KeystreamByte>,
+}
+
+impl RecoveredKeystream {
+    #[must_use]
+    pub fn keystream(&self) -> Vec<u8> {
+        self.columns.iter().map(|c| c.byte).collect()
+    }
+
+    /// Decrypt as much of the ciphertext as the keystream covers.
+    #[must_use]
+    pub fn decrypt(&self, ciphertext: &[u8]) -> Vec<u8> {
+        ciphertext
+            .iter()
+            .zip(&self.columns)
+            .map(|(b, c)| b ^ c.byte)
+            .collect()
+    }
+
+    /// The columns we shouldn't trust: those no byte decrypted to text, and those too few
+    /// ciphertexts reach for the statistics to mean much. These are usually at the tail.
+    #[must_use]
+    pub fn weak_columns(&self, min_samples: usize) -> Vec<usize> {
+        self.columns
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.score.is_none() || c.samples < min_samples)
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// How many of the best scoring keys to consider for each column.
+const CANDIDATES: usize = 5;
+
+/// Pick the keystream byte for a column. A column holds one character from each plaintext
+/// rather than words, and `score` ignores whitespace, so keys which swap common letters for
+/// each other and spaces for punctuation can score better than the real one. Of the best few
+/// keys, we take the one which decrypts the column to the most letters and spaces, counting
+/// spaces twice since they're the most common character of all.
+fn best_key(column: &[u8]) -> Option<(u8, usize)> {
+    let letters = |message: &[u8]| {
+        message
+            .iter()
+            .map(|b| match b {
+                b' ' => 2,
+                b if b.is_ascii_alphabetic() => 1,
+                _ => 0,
+            })
+            .sum::<usize>()
+    };
+
+    xor::single(column)
+        .into_iter()
+        .take(CANDIDATES)
+        .enumerate()
+        .max_by_key(|(rank, candidate)| (letters(&candidate.message), Reverse(*rank)))
+        .map(|(_, candidate)| (candidate.key, candidate.score))
+}
+
+/// Recover the keystream of many ciphertexts encrypted under the same CTR key and nonce. Each
+/// column of bytes was XORed with the same keystream byte, so it's single-byte XOR.
+#[must_use]
+pub fn break_fixed_nonce<T: AsRef<[u8]>>(
+    ciphertexts: &[T],
+    lengths: Lengths,
+) -> RecoveredKeystream {
+    let columns = match lengths {
+        Lengths::Truncate => {
+            let len = ciphertexts
+                .iter()
+                .map(|c| c.as_ref().len())
+                .min()
+                .unwrap_or(0);
+            let truncated: Vec<u8> = ciphertexts
+                .iter()
+                .flat_map(|c| &c.as_ref()[..len])
+                .copied()
+                .collect();
+
+            if len == 0 {
+                Vec::new()
+            } else {
+                transpose(&truncated, len)
+            }
+        }
+        Lengths::Ragged => {
+            let len = ciphertexts
+                .iter()
+                .map(|c| c.as_ref().len())
+                .max()
+                .unwrap_or(0);
+
+            (0..len)
+                .map(|i| {
+                    ciphertexts
+                        .iter()
+                        .filter_map(|c| c.as_ref().get(i).copied())
+                        .collect()
+                })
+                .collect()
+        }
+    };
+
+    let columns = columns
+        .into_iter()
+        .map(|column| {
+            let byte = best_key(&column);
+            KeystreamByte {
+                byte: byte.map_or(0, |(b, _)| b),
+                score: byte.map(|(_, s)| s),
+                samples: column.len(),
+            }
+        })
+        .collect();
+
+    RecoveredKeystream { columns }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAINTEXTS: [&str; 8] = [
+        "the quick brown fox jumps over the lazy dog",
+        "she sells sea shells on the sea shore",
+        "a stitch in time saves nine",
+        "all that glitters is not gold",
+        "the early bird catches the worm",
+        "where there is smoke there is fire",
+        "actions speak louder than words",
+        "better late than never",
+    ];
+
+    fn encrypt() -> Vec<Vec<u8>> {
+        let keystream: Vec<u8> = (0..64u8)
+            .map(|i| i.wrapping_mul(37).wrapping_add(11))
+            .collect();
+
+        PLAINTEXTS
+            .iter()
+            .map(|p| xor::fixed(p.as_bytes(), &keystream[..p.len()]).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_truncate() {
+        let recovered = break_fixed_nonce(&encrypt(), Lengths::Truncate);
+
+        assert_eq!(22, recovered.columns.len());
+        assert!(recovered.columns.iter().all(|c| c.samples == 8));
+    }
+
+    #[test]
+    fn test_ragged_samples() {
+        let recovered = break_fixed_nonce(&encrypt(), Lengths::Ragged);
+
+        assert_eq!(43, recovered.columns.len());
+        assert_eq!(8, recovered.columns[21].samples);
+        assert_eq!(1, recovered.columns[42].samples);
+        assert_eq!((37..43).collect::<Vec<_>>(), recovered.weak_columns(2));
+    }
+
+    #[test]
+    fn test_empty() {
+        let recovered = break_fixed_nonce::<Vec<u8>>(&[], Lengths::Truncate);
+
+        assert!(recovered.columns.is_empty());
+    }
+}
@@ -0,0 +1,71 @@
+use crate::{
+    merkle_damgard::MerkleDamgard,
+    sha1::{self, Sha1},
+};
+
+/// The block size of every hash built on `merkle_damgard::padding`, which HMAC pads keys to.
+const BLOCK_SIZE: usize = 64;
+
+/// HMAC (RFC 2104) over any of our Merkle–Damgård hashes. Unlike a secret-prefix MAC, the outer
+/// hash hides the inner state, so length extension doesn't apply.
+#[must_use]
+pub fn hmac<H: MerkleDamgard>(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut block = if key.len() > BLOCK_SIZE {
+        H::digest(key)
+    } else {
+        key.to_vec()
+    };
+    block.resize(BLOCK_SIZE, 0);
+
+    let pad = |byte: u8| block.iter().map(|b| b ^ byte).collect::<Vec<_>>();
+
+    let mut inner = H::new();
+    inner.update(&pad(0x36));
+    inner.update(message);
+
+    let mut outer = H::new();
+    outer.update(&pad(0x5c));
+    outer.update(&inner.finalize());
+    outer.finalize()
+}
+
+#[must_use]
+pub fn hmac_sha1(key: &[u8], message: &[u8]) -> [u8; sha1::DIGEST_SIZE] {
+    hmac::<Sha1>(key, message)
+        .try_into()
+        .expect("SHA-1 digests are 20 bytes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hex, md4::Md4};
+    use test_case::test_case;
+
+    // RFC 2202, test cases 1, 2 and 6
+    #[test_case(&[0x0b; 20], b"Hi There", "b617318655057264e28bc0b6fb378c8ef146be00"; "short key")]
+    #[test_case(
+        b"Jefe",
+        b"what do ya want for nothing?",
+        "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79";
+        "text key"
+    )]
+    #[test_case(
+        &[0xaa; 80],
+        b"Test Using Larger Than Block-Size Key - Hash Key First",
+        "aa4ae5e15272d00e95705637ce8a3b55ed402112";
+        "long key"
+    )]
+    fn test_hmac_sha1(key: &[u8], message: &[u8], expected: &str) {
+        assert_eq!(expected, hex::encode(&hmac_sha1(key, message)));
+    }
+
+    #[test]
+    fn test_hmac_md4() {
+        let mac = hmac::<Md4>(b"key", b"message");
+
+        assert_eq!(16, mac.len());
+        assert_ne!(mac, hmac::<Md4>(b"key", b"massage"));
+        assert_ne!(mac, hmac::<Md4>(b"kez", b"message"));
+    }
+}
@@ -0,0 +1,142 @@
+use std::fmt;
+
+use crate::{
+    aes::{self, AesError, BLOCK_SIZE},
+    bitflip::{quote, PREFIX, SUFFIX},
+    rng::RngCore,
+    xor,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReceiveError {
+    Aes(AesError),
+    /// The plaintext contained bytes above 127. The plaintext comes back with the error, which
+    /// is exactly the leak the key recovery relies on.
+    HighAscii(Vec<u8>),
+}
+
+impl fmt::Display for ReceiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Aes(err) => write!(f, "{err}"),
+            Self::HighAscii(plaintext) => {
+                write!(
+                    f,
+                    "invalid plaintext: {}",
+                    String::from_utf8_lossy(plaintext)
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReceiveError {}
+
+impl From<AesError> for ReceiveError {
+    fn from(err: AesError) -> Self {
+        Self::Aes(err)
+    }
+}
+
+/// Encrypts the challenge 16 cookie with AES-CBC under a random key, which it also uses as the
+/// IV.
+pub struct KeyAsIvOracle {
+    key: Vec<u8>,
+}
+
+impl KeyAsIvOracle {
+    pub fn new<R: RngCore>(rng: &mut R) -> Self {
+        Self {
+            key: rng.bytes(BLOCK_SIZE),
+        }
+    }
+
+    #[must_use]
+    pub fn encrypt(&self, userdata: &[u8]) -> Vec<u8> {
+        let plaintext = [PREFIX, &quote(userdata), SUFFIX].concat();
+
+        aes::cbc_encrypt_key_as_iv(&self.key, &plaintext).expect("Key is one block long")
+    }
+
+    /// Decrypt a ciphertext and check that its plaintext is plain ASCII.
+    pub fn receive(&self, ciphertext: &[u8]) -> Result<(), ReceiveError> {
+        let plaintext = aes::cbc_decrypt_key_as_iv(&self.key, ciphertext)?;
+
+        if plaintext.is_ascii() {
+            Ok(())
+        } else {
+            Err(ReceiveError::HighAscii(plaintext))
+        }
+    }
+}
+
+/// Recover the key from a receiver which uses it as the IV, given a ciphertext of at least five
+/// blocks.
+///
+/// We send `C1, 0, C1` followed by the rest of the ciphertext from the fourth block on, which
+/// keeps the padding valid. The first block decrypts to `D(C1) ^ key` and the third to
+/// `D(C1) ^ 0`, so XORing them gives the key. The second block decrypts to garbage which
+/// almost certainly trips the high-ASCII check, and the error hands us the plaintext.
+pub fn recover_key<F>(ciphertext: &[u8], receive: F) -> Option<Vec<u8>>
+where
+    F: Fn(&[u8]) -> Result<(), ReceiveError>,
+{
+    if ciphertext.len() < 5 * BLOCK_SIZE {
+        return None;
+    }
+
+    let first = &ciphertext[..BLOCK_SIZE];
+    let tampered = [
+        first,
+        &[0; BLOCK_SIZE],
+        first,
+        &ciphertext[3 * BLOCK_SIZE..],
+    ]
+    .concat();
+
+    match receive(&tampered) {
+        Err(ReceiveError::HighAscii(plaintext)) => xor::fixed(
+            plaintext.get(..BLOCK_SIZE)?,
+            plaintext.get(2 * BLOCK_SIZE..3 * BLOCK_SIZE)?,
+        ),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    #[test]
+    fn test_receive_accepts_ascii() {
+        let oracle = KeyAsIvOracle::new(&mut SplitMix64::new(27));
+
+        assert_eq!(Ok(()), oracle.receive(&oracle.encrypt(b"hello")));
+    }
+
+    #[test]
+    fn test_receive_returns_high_ascii_plaintext() {
+        let oracle = KeyAsIvOracle::new(&mut SplitMix64::new(27));
+        let ciphertext = oracle.encrypt("caf\u{e9}".as_bytes());
+
+        let err = oracle.receive(&ciphertext).unwrap_err();
+
+        assert_eq!(
+            ReceiveError::HighAscii([PREFIX, "caf\u{e9}".as_bytes(), SUFFIX].concat()),
+            err
+        );
+    }
+
+    #[test]
+    fn test_recover_key_needs_five_blocks() {
+        assert_eq!(None, recover_key(&[0; 4 * BLOCK_SIZE], |_| Ok(())));
+    }
+
+    #[test]
+    fn test_recover_key_from_short_plaintext() {
+        let receive = |_: &[u8]| Err(ReceiveError::HighAscii(vec![0x80; 3]));
+
+        assert_eq!(None, recover_key(&[0; 5 * BLOCK_SIZE], receive));
+    }
+}
@@ -1,7 +1,27 @@
 pub mod aes;
 pub mod base64;
+pub mod bigint;
+pub mod bitflip;
+pub mod byte_at_a_time;
+pub mod cookie;
+pub mod crib;
+pub mod ctr_edit;
+pub mod dh;
+pub mod fixed_nonce;
 pub mod hex;
+pub mod hmac;
+pub mod key_as_iv;
+pub mod md4;
+pub mod merkle_damgard;
+pub mod mode_detection;
+pub mod mt_cipher;
+pub mod mt_crack;
+pub mod padding;
+pub mod padding_oracle;
+pub mod rng;
+pub mod sha1;
 pub mod sorted;
+pub mod timing_leak;
 pub mod xor;
 
 mod crack_utils;
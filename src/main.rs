@@ -1,8 +1,12 @@
-use cryptopals::base64;
+use cryptopals::{aes, hex};
 
 fn main() {
-    let solution =
-        &cryptopals::aes::aes_crack(&base64::decode(include_str!("../data/8.txt")), 2, 40)[0];
+    let ciphertexts: Vec<_> = include_str!("../data/8.txt")
+        .lines()
+        .map(|l| hex::decode(l.trim()))
+        .collect();
+
+    let solution = &aes::detect_ecb(&ciphertexts)[0];
 
     println!("{solution:?}");
 }
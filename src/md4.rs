@@ -0,0 +1,227 @@
+use crate::merkle_damgard::{self, ByteOrder, Hasher, MerkleDamgard};
+
+/// The size of the blocks MD4 compresses, in bytes.
+pub const BLOCK_SIZE: usize = merkle_damgard::BLOCK_SIZE;
+
+/// The size of an MD4 digest, in bytes.
+pub const DIGEST_SIZE: usize = 16;
+
+/// A round's boolean function, additive constant, message word order and shift amounts.
+type Round<'a> = (&'a dyn Fn(u32, u32, u32) -> u32, u32, [usize; 16], [u32; 4]);
+
+const INITIAL_STATE: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+/// An MD4 hasher, written from RFC 1320. Like `Sha1`, its state can be read out and resumed
+/// from, but MD4 is little-endian throughout.
+#[derive(Debug, Clone)]
+pub struct Md4 {
+    hasher: Hasher<4>,
+}
+
+impl Default for Md4 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Md4 {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from_state(INITIAL_STATE, 0)
+    }
+
+    /// Resume hashing from a state, as if `length` bytes had already been hashed to get it.
+    ///
+    /// # Panics
+    ///
+    /// If `length` isn't a whole number of blocks.
+    #[must_use]
+    pub fn from_state(state: [u32; 4], length: u64) -> Self {
+        let hasher = Hasher::from_state(state, length, ByteOrder::LittleEndian, compress)
+            .expect("MD4 can only resume at a block boundary");
+
+        Self { hasher }
+    }
+
+    /// The four registers, as they stand after the last complete block.
+    #[must_use]
+    pub fn state(&self) -> [u32; 4] {
+        self.hasher.state()
+    }
+
+    /// The number of bytes hashed so far.
+    #[must_use]
+    pub fn length(&self) -> u64 {
+        self.hasher.length()
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+    }
+
+    #[must_use]
+    pub fn finalize(self) -> [u8; DIGEST_SIZE] {
+        self.hasher
+            .finalize()
+            .try_into()
+            .expect("The registers fill the digest")
+    }
+}
+
+fn compress(state: &mut [u32; 4], block: &[u8]) {
+    let mut x = [0u32; 16];
+    for (word, chunk) in x.iter_mut().zip(block.chunks(4)) {
+        *word = u32::from_le_bytes(chunk.try_into().expect("Chunks are four bytes"));
+    }
+
+    let f = |x: u32, y: u32, z: u32| (x & y) | (!x & z);
+    let g = |x: u32, y: u32, z: u32| (x & y) | (x & z) | (y & z);
+    let h = |x: u32, y: u32, z: u32| x ^ y ^ z;
+
+    let rounds: [Round; 3] = [
+        (
+            &f,
+            0,
+            [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+            [3, 7, 11, 19],
+        ),
+        (
+            &g,
+            0x5a827999,
+            [0, 4, 8, 12, 1, 5, 9, 13, 2, 6, 10, 14, 3, 7, 11, 15],
+            [3, 5, 9, 13],
+        ),
+        (
+            &h,
+            0x6ed9eba1,
+            [0, 8, 4, 12, 2, 10, 6, 14, 1, 9, 5, 13, 3, 11, 7, 15],
+            [3, 9, 11, 15],
+        ),
+    ];
+
+    // Each step updates one register from the other three, cycling through a, d, c, b
+    let mut v = *state;
+    for (function, constant, order, shifts) in rounds {
+        for (step, k) in order.into_iter().enumerate() {
+            let t = (4 - step % 4) % 4;
+            v[t] = v[t]
+                .wrapping_add(function(v[(t + 1) % 4], v[(t + 2) % 4], v[(t + 3) % 4]))
+                .wrapping_add(x[k])
+                .wrapping_add(constant)
+                .rotate_left(shifts[step % 4]);
+        }
+    }
+
+    for (s, v) in state.iter_mut().zip(v) {
+        *s = s.wrapping_add(v);
+    }
+}
+
+/// The padding MD4 appends to a message of `length` bytes, with the length little-endian.
+#[must_use]
+pub fn padding(length: u64) -> Vec<u8> {
+    merkle_damgard::padding(length, ByteOrder::LittleEndian)
+}
+
+impl MerkleDamgard for Md4 {
+    fn new() -> Self {
+        Self::new()
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.update(data);
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        self.finalize().to_vec()
+    }
+
+    fn padding(length: u64) -> Vec<u8> {
+        padding(length)
+    }
+
+    fn from_digest(digest: &[u8], length: u64) -> Option<Self> {
+        let state = merkle_damgard::state_from_digest(digest, ByteOrder::LittleEndian)?;
+        let hasher = Hasher::from_state(state, length, ByteOrder::LittleEndian, compress)?;
+
+        Some(Self { hasher })
+    }
+}
+
+#[must_use]
+pub fn md4(data: &[u8]) -> [u8; DIGEST_SIZE] {
+    let mut hasher = Md4::new();
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// Authenticate a message by hashing it behind a secret key, which is just as broken as with
+/// SHA-1.
+#[must_use]
+pub fn secret_prefix_mac(key: &[u8], message: &[u8]) -> [u8; DIGEST_SIZE] {
+    let mut hasher = Md4::new();
+    hasher.update(key);
+    hasher.update(message);
+    hasher.finalize()
+}
+
+#[must_use]
+pub fn verify_secret_prefix_mac(key: &[u8], message: &[u8], mac: &[u8]) -> bool {
+    secret_prefix_mac(key, message) == mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex;
+    use test_case::test_case;
+
+    // RFC 1320, appendix A.5
+    #[test_case(b"", "31d6cfe0d16ae931b73c59d7e0c089c0"; "empty")]
+    #[test_case(b"a", "bde52cb31de33e46245e05fbdbd6fb24"; "a")]
+    #[test_case(b"abc", "a448017aaf21d8525fc10ae87aa6729d"; "abc")]
+    #[test_case(b"message digest", "d9130a8164549fe818874806e1c7014b"; "message digest")]
+    #[test_case(b"abcdefghijklmnopqrstuvwxyz", "d79e1c308aa5bbcdeea8ed63df412da9"; "alphabet")]
+    #[test_case(
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+        "043f8582f241db351ce627e153e7f0e4";
+        "alphanumeric"
+    )]
+    #[test_case(
+        b"12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+        "e33b4ddc9c38f2199c3e7b164fcc0536";
+        "digits"
+    )]
+    fn test_md4(input: &[u8], expected: &str) {
+        assert_eq!(expected, hex::encode(&md4(input)));
+    }
+
+    #[test]
+    fn test_resume_from_state() {
+        let message = [b'x'; 3 * BLOCK_SIZE];
+        let mut hasher = Md4::new();
+        hasher.update(&message[..BLOCK_SIZE]);
+
+        let mut resumed = Md4::from_state(hasher.state(), hasher.length());
+        resumed.update(&message[BLOCK_SIZE..]);
+
+        assert_eq!(md4(&message), resumed.finalize());
+    }
+
+    #[test]
+    fn test_from_digest_rejects_bad_input() {
+        let digest = [0; DIGEST_SIZE];
+
+        assert!(<Md4 as MerkleDamgard>::from_digest(&digest[1..], 0).is_none());
+        assert!(<Md4 as MerkleDamgard>::from_digest(&digest, 5).is_none());
+        assert!(<Md4 as MerkleDamgard>::from_digest(&digest, BLOCK_SIZE as u64).is_some());
+    }
+
+    #[test]
+    fn test_secret_prefix_mac() {
+        let mac = secret_prefix_mac(b"key", b"message");
+
+        assert!(verify_secret_prefix_mac(b"key", b"message", &mac));
+        assert!(!verify_secret_prefix_mac(b"key", b"massage", &mac));
+    }
+}
@@ -0,0 +1,232 @@
+/// The size of the blocks the hashes here compress, in bytes.
+pub const BLOCK_SIZE: usize = 64;
+
+/// The order in which a hash reads and writes its words, including the message length in its
+/// padding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteOrder {
+    BigEndian,
+    LittleEndian,
+}
+
+/// The padding a Merkle–Damgård hash with 64-byte blocks and a 64-bit length (MD4, MD5, SHA-1,
+/// SHA-256) appends to a message of `length` bytes: a 1 bit, zeros up to 8 bytes short of a
+/// block boundary, then the message length in bits.
+#[must_use]
+pub fn padding(length: u64, order: ByteOrder) -> Vec<u8> {
+    let zeros = (64 * 2 - 9 - (length % 64) as usize) % 64;
+    let bits = length.wrapping_mul(8);
+    let encoded = match order {
+        ByteOrder::BigEndian => bits.to_be_bytes(),
+        ByteOrder::LittleEndian => bits.to_le_bytes(),
+    };
+
+    [&[0x80], vec![0; zeros].as_slice(), &encoded].concat()
+}
+
+/// A compression function, which folds one block into the registers.
+pub type Compress<const N: usize> = fn(&mut [u32; N], &[u8]);
+
+/// What every Merkle–Damgård hash with 64-byte blocks and `N` 32-bit registers has in common:
+/// it buffers input into blocks, counts how much it's seen and pads the message at the end. The
+/// hash itself only brings its compression function and byte order.
+#[derive(Debug, Clone)]
+pub struct Hasher<const N: usize> {
+    state: [u32; N],
+    length: u64,
+    buffer: Vec<u8>,
+    order: ByteOrder,
+    compress: Compress<N>,
+}
+
+impl<const N: usize> Hasher<N> {
+    /// Start from a state, as if `length` bytes had already been hashed to get it. `None` if
+    /// `length` isn't a whole number of blocks, since the registers only change at the end of a
+    /// block.
+    #[must_use]
+    pub fn from_state(
+        state: [u32; N],
+        length: u64,
+        order: ByteOrder,
+        compress: Compress<N>,
+    ) -> Option<Self> {
+        (length % BLOCK_SIZE as u64 == 0).then(|| Self {
+            state,
+            length,
+            buffer: Vec::with_capacity(BLOCK_SIZE),
+            order,
+            compress,
+        })
+    }
+
+    /// The registers, as they stand after the last complete block.
+    #[must_use]
+    pub fn state(&self) -> [u32; N] {
+        self.state
+    }
+
+    /// The number of bytes hashed so far.
+    #[must_use]
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.length += data.len() as u64;
+
+        if !self.buffer.is_empty() {
+            let take = data.len().min(BLOCK_SIZE - self.buffer.len());
+            self.buffer.extend_from_slice(&data[..take]);
+            data = &data[take..];
+
+            if self.buffer.len() < BLOCK_SIZE {
+                return;
+            }
+            let block = std::mem::take(&mut self.buffer);
+            (self.compress)(&mut self.state, &block);
+        }
+
+        let mut blocks = data.chunks_exact(BLOCK_SIZE);
+        for block in &mut blocks {
+            (self.compress)(&mut self.state, block);
+        }
+        self.buffer.extend_from_slice(blocks.remainder());
+    }
+
+    /// Pad the message and write out the registers, `4 * N` bytes in all.
+    #[must_use]
+    pub fn finalize(mut self) -> Vec<u8> {
+        let length = self.length;
+        self.update(&padding(length, self.order));
+
+        self.state
+            .iter()
+            .flat_map(|word| match self.order {
+                ByteOrder::BigEndian => word.to_be_bytes(),
+                ByteOrder::LittleEndian => word.to_le_bytes(),
+            })
+            .collect()
+    }
+}
+
+/// Read the registers back out of a digest. `None` if the digest is the wrong size.
+#[must_use]
+pub fn state_from_digest<const N: usize>(digest: &[u8], order: ByteOrder) -> Option<[u32; N]> {
+    if digest.len() != 4 * N {
+        return None;
+    }
+
+    let mut state = [0; N];
+    for (word, chunk) in state.iter_mut().zip(digest.chunks_exact(4)) {
+        let bytes = chunk.try_into().ok()?;
+        *word = match order {
+            ByteOrder::BigEndian => u32::from_be_bytes(bytes),
+            ByteOrder::LittleEndian => u32::from_le_bytes(bytes),
+        };
+    }
+
+    Some(state)
+}
+
+/// A Merkle–Damgård hash which can pick up where a digest left off. The digest of a message is
+/// just the hash's state after the padded message, so resuming from it lets anyone append to
+/// the message without knowing what it was.
+pub trait MerkleDamgard: Sized {
+    fn new() -> Self;
+    fn update(&mut self, data: &[u8]);
+    fn finalize(self) -> Vec<u8>;
+
+    /// The padding the hash appends to a message of `length` bytes.
+    fn padding(length: u64) -> Vec<u8>;
+
+    /// Resume from a digest, as if `length` bytes had been hashed to get it. `None` if the
+    /// digest is the wrong size or `length` isn't a whole number of blocks.
+    fn from_digest(digest: &[u8], length: u64) -> Option<Self>;
+
+    fn digest(data: &[u8]) -> Vec<u8> {
+        let mut hasher = Self::new();
+        hasher.update(data);
+        hasher.finalize()
+    }
+}
+
+/// A message and MAC forged by length extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Forgery {
+    pub message: Vec<u8>,
+    pub mac: Vec<u8>,
+    /// The key length the forgery assumed.
+    pub key_len: usize,
+}
+
+/// Extend a message authenticated with `H(key || message)`, assuming a key of `key_len`
+/// bytes. The forged message is the original, its glue padding, then the extension, and its
+/// MAC comes from hashing the extension on from the original MAC.
+#[must_use]
+pub fn extend<H: MerkleDamgard>(
+    message: &[u8],
+    mac: &[u8],
+    extension: &[u8],
+    key_len: usize,
+) -> Option<Forgery> {
+    let glue = H::padding((key_len + message.len()) as u64);
+    let processed = key_len + message.len() + glue.len();
+
+    let mut hasher = H::from_digest(mac, processed as u64)?;
+    hasher.update(extension);
+
+    Some(Forgery {
+        message: [message, &glue, extension].concat(),
+        mac: hasher.finalize(),
+        key_len,
+    })
+}
+
+/// Forge a MAC for the message with the extension appended, trying each key length up to
+/// `max_key_len` until `verify` accepts the forgery.
+pub fn forge<H, F>(
+    message: &[u8],
+    mac: &[u8],
+    extension: &[u8],
+    max_key_len: usize,
+    verify: F,
+) -> Option<Forgery>
+where
+    H: MerkleDamgard,
+    F: Fn(&[u8], &[u8]) -> bool,
+{
+    (0..=max_key_len)
+        .filter_map(|key_len| extend::<H>(message, mac, extension, key_len))
+        .find(|forgery| verify(&forgery.message, &forgery.mac))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_padding() {
+        let padding = padding(3, ByteOrder::BigEndian);
+
+        assert_eq!(61, padding.len());
+        assert_eq!(0x80, padding[0]);
+        assert_eq!([0, 0, 0, 0, 0, 0, 0, 24], padding[53..]);
+    }
+
+    #[test]
+    fn test_padding_little_endian() {
+        let padding = padding(3, ByteOrder::LittleEndian);
+
+        assert_eq!([24, 0, 0, 0, 0, 0, 0, 0], padding[53..]);
+    }
+
+    #[test]
+    fn test_padding_spills_into_another_block() {
+        for length in [55, 56, 63, 64] {
+            let padding = padding(length, ByteOrder::BigEndian);
+
+            assert_eq!(0, (length as usize + padding.len()) % 64);
+            assert!(padding.len() >= 9);
+        }
+    }
+}
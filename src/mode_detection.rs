@@ -0,0 +1,99 @@
+use crate::{
+    aes::{self, BLOCK_SIZE},
+    rng::RngCore,
+};
+
+/// A block cipher mode of operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Ecb,
+    Cbc,
+}
+
+/// An oracle which surrounds its input with 5-10 random bytes on each side, then encrypts it
+/// under a fresh random key with either ECB or CBC, chosen at random. The mode used for each
+/// call is recorded so that detectors can be checked against it.
+pub struct EncryptionOracle<R> {
+    rng: R,
+    history: Vec<Mode>,
+}
+
+impl<R: RngCore> EncryptionOracle<R> {
+    pub fn new(rng: R) -> Self {
+        Self {
+            rng,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn encrypt(&mut self, input: &[u8]) -> Vec<u8> {
+        let key = self.rng.bytes(BLOCK_SIZE);
+
+        let prefix_len = self.rng.gen_range(5..11);
+        let suffix_len = self.rng.gen_range(5..11);
+        let plaintext = [
+            self.rng.bytes(prefix_len),
+            input.to_vec(),
+            self.rng.bytes(suffix_len),
+        ]
+        .concat();
+
+        let (mode, ciphertext) = if self.rng.gen_bool() {
+            (Mode::Ecb, aes::ecb_encrypt_padded(&key, &plaintext))
+        } else {
+            let iv = self.rng.bytes(BLOCK_SIZE);
+            (Mode::Cbc, aes::cbc_encrypt(&key, &iv, &plaintext))
+        };
+
+        self.history.push(mode);
+
+        ciphertext.expect("Key and IV are one block long")
+    }
+
+    /// The modes used so far, in the order the calls were made.
+    pub fn history(&self) -> &[Mode] {
+        &self.history
+    }
+}
+
+/// Work out whether an oracle encrypts with ECB or CBC. Three identical blocks of input are
+/// enough to fill at least two whole blocks even after a prefix of up to one block, and those
+/// will encrypt to identical ciphertext blocks under ECB only.
+pub fn detect_mode<F>(mut oracle: F) -> Mode
+where
+    F: FnMut(&[u8]) -> Vec<u8>,
+{
+    let ciphertext = oracle(&[b'A'; 3 * BLOCK_SIZE]);
+
+    if aes::repeated_blocks(&ciphertext).is_empty() {
+        Mode::Cbc
+    } else {
+        Mode::Ecb
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    #[test]
+    fn test_oracle_is_deterministic_for_a_seed() {
+        let mut oracle1 = EncryptionOracle::new(SplitMix64::new(7));
+        let mut oracle2 = EncryptionOracle::new(SplitMix64::new(7));
+
+        assert_eq!(oracle1.encrypt(b"input"), oracle2.encrypt(b"input"));
+        assert_eq!(oracle1.history(), oracle2.history());
+    }
+
+    #[test]
+    fn test_oracle_output_length() {
+        let mut oracle = EncryptionOracle::new(SplitMix64::new(7));
+
+        for _ in 0..20 {
+            let len = oracle.encrypt(b"").len();
+
+            assert!(len == 16 || len == 32);
+        }
+    }
+}
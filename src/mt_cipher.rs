@@ -0,0 +1,82 @@
+use crate::{
+    mt_crack::Clock,
+    rng::{Mt19937, RngCore},
+    xor,
+};
+
+/// The keystream of the MT19937 stream cipher: the generator's outputs as little-endian bytes.
+fn keystream(seed: u32, len: usize) -> Vec<u8> {
+    Mt19937::new(seed).bytes(len)
+}
+
+/// Encrypt or decrypt with a stream cipher keyed by a 16-bit seed for MT19937.
+#[must_use]
+pub fn mt_stream(seed: u16, data: &[u8]) -> Vec<u8> {
+    xor::fixed(data, &keystream(seed.into(), data.len())).expect("Keystream matches the data")
+}
+
+/// Encrypt a known plaintext behind 5 to 20 random bytes, as in challenge 24.
+pub fn encrypt_with_random_prefix<R: RngCore>(rng: &mut R, seed: u16, known: &[u8]) -> Vec<u8> {
+    let prefix_len = rng.gen_range(5..21);
+    let plaintext = [rng.bytes(prefix_len), known.to_vec()].concat();
+
+    mt_stream(seed, &plaintext)
+}
+
+/// Recover the seed of an MT19937 stream cipher from a ciphertext ending in known plaintext,
+/// by trying all 65536 of them.
+#[must_use]
+pub fn recover_seed(ciphertext: &[u8], known_suffix: &[u8]) -> Option<u16> {
+    // Every seed would match an empty suffix
+    if known_suffix.is_empty() {
+        return None;
+    }
+    let offset = ciphertext.len().checked_sub(known_suffix.len())?;
+    let target = xor::fixed(&ciphertext[offset..], known_suffix)?;
+
+    (0..=u16::MAX).find(|&seed| keystream(seed.into(), ciphertext.len())[offset..] == target)
+}
+
+/// A password reset token: the keystream of an MT19937 seeded with the current time.
+pub fn reset_token<C: Clock>(clock: &C, len: usize) -> Vec<u8> {
+    keystream(clock.now() as u32, len)
+}
+
+/// Whether a token came from an MT19937 seeded with a timestamp within `window` seconds
+/// before the clock's current time.
+pub fn is_time_seeded_token<C: Clock>(token: &[u8], clock: &C, window: u64) -> bool {
+    // Every keystream starts with an empty token
+    if token.is_empty() {
+        return false;
+    }
+    let now = clock.now();
+
+    (now.saturating_sub(window)..=now).any(|t| keystream(t as u32, token.len()) == token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mt_crack::FakeClock;
+
+    #[test]
+    fn test_mt_stream_round_trip() {
+        let plaintext = b"Attack at dawn, or maybe a bit later";
+
+        let ciphertext = mt_stream(0xbeef, plaintext);
+
+        assert_ne!(plaintext.to_vec(), ciphertext);
+        assert_eq!(plaintext.to_vec(), mt_stream(0xbeef, &ciphertext));
+    }
+
+    #[test]
+    fn test_recover_seed_needs_a_suffix_that_fits() {
+        assert_eq!(None, recover_seed(b"short", b"too long"));
+        assert_eq!(None, recover_seed(b"short", b""));
+    }
+
+    #[test]
+    fn test_empty_token_is_not_time_seeded() {
+        assert!(!is_time_seeded_token(&[], &FakeClock::new(1000), 10));
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    cell::Cell,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::rng::{Mt19937, RngCore, N};
+
+/// A source of Unix timestamps, in seconds, which can also be waited on.
+pub trait Clock {
+    fn now(&self) -> u64;
+    fn sleep(&self, seconds: u64);
+}
+
+/// The real clock.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs())
+    }
+
+    fn sleep(&self, seconds: u64) {
+        thread::sleep(Duration::from_secs(seconds));
+    }
+}
+
+/// A clock which only moves when slept on, so tests don't have to wait.
+pub struct FakeClock {
+    now: Cell<u64>,
+}
+
+impl FakeClock {
+    #[must_use]
+    pub fn new(now: u64) -> Self {
+        Self {
+            now: Cell::new(now),
+        }
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> u64 {
+        self.now.get()
+    }
+
+    fn sleep(&self, seconds: u64) {
+        self.now.set(self.now.get() + seconds);
+    }
+}
+
+/// The routine from challenge 22: wait 40 to 1000 seconds, seed an MT19937 with the current
+/// timestamp, wait 40 to 1000 seconds again, and return the generator's first output.
+pub fn timestamp_seeded_output<C: Clock, R: RngCore>(clock: &C, rng: &mut R) -> u32 {
+    clock.sleep(rng.gen_range(40..1001) as u64);
+    let mut mt = Mt19937::new(clock.now() as u32);
+    clock.sleep(rng.gen_range(40..1001) as u64);
+
+    mt.next_u32()
+}
+
+/// Find the seed of an MT19937 whose first output we have, given that it was seeded with a
+/// timestamp at most `window` seconds before the clock's current time. The candidate seeds are
+/// split between `threads` threads, which all stop as soon as one of them finds the seed.
+///
+/// # Panics
+///
+/// If `threads` is zero.
+pub fn crack_timestamp_seed<C: Clock>(
+    output: u32,
+    clock: &C,
+    window: u64,
+    threads: usize,
+) -> Option<u32> {
+    assert!(threads > 0, "Need at least one thread to search with");
+
+    let now = clock.now();
+    let start = now.saturating_sub(window);
+    let chunk = (now - start) / threads as u64 + 1;
+    let found = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads as u64)
+            .map(|t| {
+                let found = &found;
+                scope.spawn(move || {
+                    let from = start + t * chunk;
+                    let to = (from + chunk).min(now + 1);
+
+                    (from..to).map(|seed| seed as u32).find(|&seed| {
+                        if found.load(Ordering::Relaxed) {
+                            return false;
+                        }
+                        let hit = Mt19937::new(seed).next_u32() == output;
+                        if hit {
+                            found.store(true, Ordering::Relaxed);
+                        }
+                        hit
+                    })
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .filter_map(|handle| handle.join().expect("Search thread panicked"))
+            .next()
+    })
+}
+
+fn undo_right_shift(y: u32, shift: u32) -> u32 {
+    // Each pass recovers another `shift` bits, starting from the top ones, which are unchanged
+    (0..32 / shift).fold(y, |x, _| y ^ (x >> shift))
+}
+
+fn undo_left_shift(y: u32, shift: u32, mask: u32) -> u32 {
+    (0..32 / shift).fold(y, |x, _| y ^ ((x << shift) & mask))
+}
+
+/// Invert MT19937's tempering, recovering the word of state behind an output.
+#[must_use]
+pub fn untemper(y: u32) -> u32 {
+    let y = undo_right_shift(y, 18);
+    let y = undo_left_shift(y, 15, 0xefc60000);
+    let y = undo_left_shift(y, 7, 0x9d2c5680);
+    undo_right_shift(y, 11)
+}
+
+/// Clone an MT19937 from at least 624 consecutive outputs, returning a generator which
+/// predicts every output after the last one given.
+///
+/// Untempering 624 consecutive outputs gives 624 consecutive words of state. Twisting is the
+/// same recurrence wherever a block of state starts, so those words make a working state even
+/// when the outputs don't start at a twist. The outputs may also start with values which
+/// aren't from the generator: we try each offset into the outputs as the start of the state,
+/// and keep the first whose clone predicts all the outputs after it.
+#[must_use]
+pub fn clone_from_outputs(outputs: &[u32]) -> Option<Mt19937> {
+    (0..=outputs.len().checked_sub(N)?).find_map(|offset| {
+        let mut state = [0; N];
+        for (word, &output) in state.iter_mut().zip(&outputs[offset..offset + N]) {
+            *word = untemper(output);
+        }
+
+        let mut clone = Mt19937::from_state(state);
+        outputs[offset + N..]
+            .iter()
+            .all(|&output| clone.next_u32() == output)
+            .then_some(clone)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::{temper, SplitMix64};
+
+    #[test]
+    fn test_fake_clock() {
+        let clock = FakeClock::new(100);
+        clock.sleep(40);
+
+        assert_eq!(140, clock.now());
+    }
+
+    #[test]
+    fn test_crack_at_window_edges() {
+        let clock = FakeClock::new(1_700_000_000);
+
+        for seed in [1_700_000_000 - 500, 1_700_000_000] {
+            let output = Mt19937::new(seed).next_u32();
+
+            assert_eq!(Some(seed), crack_timestamp_seed(output, &clock, 500, 3));
+        }
+    }
+
+    #[test]
+    fn test_crack_outside_window() {
+        let clock = FakeClock::new(1_700_000_000);
+        let output = Mt19937::new(1_700_000_000 - 501).next_u32();
+
+        assert_eq!(None, crack_timestamp_seed(output, &clock, 500, 4));
+    }
+
+    #[test]
+    fn test_untemper() {
+        let mut rng = SplitMix64::new(23);
+
+        for _ in 0..1000 {
+            let y = rng.next_u32();
+
+            assert_eq!(y, untemper(temper(y)));
+        }
+    }
+
+    #[test]
+    fn test_clone_mid_state() {
+        let mut mt = Mt19937::new(5489);
+        for _ in 0..100 {
+            mt.next_u32();
+        }
+        let outputs: Vec<_> = (0..N + 10).map(|_| mt.next_u32()).collect();
+
+        let mut clone = clone_from_outputs(&outputs).unwrap();
+
+        for _ in 0..2000 {
+            assert_eq!(mt.next_u32(), clone.next_u32());
+        }
+    }
+
+    #[test]
+    fn test_clone_skips_unrelated_outputs() {
+        let mut mt = Mt19937::new(42);
+        let outputs: Vec<_> = [1, 2, 3]
+            .into_iter()
+            .chain((0..N + 20).map(|_| mt.next_u32()))
+            .collect();
+
+        let mut clone = clone_from_outputs(&outputs).unwrap();
+
+        assert_eq!(mt.next_u32(), clone.next_u32());
+    }
+
+    #[test]
+    fn test_clone_needs_enough_outputs() {
+        assert!(clone_from_outputs(&[0; N - 1]).is_none());
+    }
+}
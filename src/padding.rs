@@ -0,0 +1,122 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaddingError {
+    /// There were no bytes to unpad.
+    Empty,
+    /// The last byte was zero, which PKCS#7 never produces.
+    ZeroPadByte,
+    /// The last byte claimed more padding than a block (or the input) can hold.
+    PadByteTooLarge(u8),
+    /// The padding bytes didn't all equal the pad length.
+    InconsistentPadding,
+}
+
+impl fmt::Display for PaddingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "cannot unpad empty input"),
+            Self::ZeroPadByte => write!(f, "pad byte is zero"),
+            Self::PadByteTooLarge(b) => write!(f, "pad byte {b} is larger than the block"),
+            Self::InconsistentPadding => write!(f, "padding bytes are inconsistent"),
+        }
+    }
+}
+
+impl std::error::Error for PaddingError {}
+
+/// Pad the bytes to a multiple of `block_size` with PKCS#7. A full block of padding is added
+/// when the input is already aligned, so that unpadding is never ambiguous.
+///
+/// # Panics
+///
+/// If `block_size` isn't between 1 and 255.
+#[must_use]
+pub fn pkcs7_pad(bytes: &[u8], block_size: usize) -> Vec<u8> {
+    assert!(
+        (1..=255).contains(&block_size),
+        "PKCS#7 block size must be between 1 and 255"
+    );
+
+    let pad = block_size - bytes.len() % block_size;
+    let mut padded = Vec::with_capacity(bytes.len() + pad);
+    padded.extend_from_slice(bytes);
+    padded.resize(bytes.len() + pad, pad as u8);
+
+    padded
+}
+
+/// Strip and validate PKCS#7 padding.
+pub fn pkcs7_unpad(bytes: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+    let &last = bytes.last().ok_or(PaddingError::Empty)?;
+    let pad = usize::from(last);
+
+    if pad == 0 {
+        return Err(PaddingError::ZeroPadByte);
+    }
+
+    if pad > block_size || pad > bytes.len() {
+        return Err(PaddingError::PadByteTooLarge(last));
+    }
+
+    let (message, padding) = bytes.split_at(bytes.len() - pad);
+    if padding.iter().any(|&b| b != last) {
+        return Err(PaddingError::InconsistentPadding);
+    }
+
+    Ok(message.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test]
+    fn test_pad() {
+        assert_eq!(
+            b"YELLOW SUBMARINE\x04\x04\x04\x04".as_slice(),
+            pkcs7_pad(b"YELLOW SUBMARINE", 20)
+        );
+    }
+
+    #[test]
+    fn test_pad_adds_a_full_block_when_aligned() {
+        assert_eq!(vec![8; 8], pkcs7_pad(b"", 8));
+        assert_eq!(
+            b"YELLOW SUBMARINE\x10\x10\x10\x10\x10\x10\x10\x10\x10\x10\x10\x10\x10\x10\x10\x10"
+                .as_slice(),
+            pkcs7_pad(b"YELLOW SUBMARINE", 16)
+        );
+    }
+
+    #[test]
+    fn test_unpad() {
+        assert_eq!(
+            Ok(b"ICE ICE BABY".to_vec()),
+            pkcs7_unpad(b"ICE ICE BABY\x04\x04\x04\x04", 16)
+        );
+    }
+
+    #[test_case(b"", PaddingError::Empty; "empty")]
+    #[test_case(b"ICE ICE BABY\x04\x04\x04\x00", PaddingError::ZeroPadByte; "zero byte")]
+    #[test_case(b"ICE ICE BABY\x04\x04\x04\x11", PaddingError::PadByteTooLarge(0x11); "too large")]
+    #[test_case(b"\x03\x03", PaddingError::PadByteTooLarge(3); "longer than input")]
+    #[test_case(b"ICE ICE BABY\x05\x05\x05\x05", PaddingError::InconsistentPadding; "inconsistent")]
+    #[test_case(b"ICE ICE BABY\x01\x02\x03\x04", PaddingError::InconsistentPadding; "ascending")]
+    fn test_unpad_rejects(input: &[u8], expected: PaddingError) {
+        assert_eq!(Err(expected), pkcs7_unpad(input, 16));
+    }
+
+    #[test]
+    fn test_round_trip_all_block_sizes() {
+        let message = b"Lorem ipsum dolor sit amet";
+
+        for block_size in 1..=255 {
+            let padded = pkcs7_pad(message, block_size);
+
+            assert_eq!(0, padded.len() % block_size);
+            assert_eq!(Ok(message.to_vec()), pkcs7_unpad(&padded, block_size));
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use std::{cell::Cell, fmt};
+
+use crate::{
+    aes::{self, BLOCK_SIZE},
+    padding::{pkcs7_unpad, PaddingError},
+    rng::RngCore,
+    xor,
+};
+
+/// Something which tells us whether a CBC ciphertext decrypts to validly padded plaintext,
+/// and nothing else.
+pub trait PaddingOracle {
+    fn check(&self, iv: &[u8], ct: &[u8]) -> bool;
+}
+
+/// An in-process padding oracle: a server holding an AES key which hands out CBC ciphertexts
+/// and only reports whether ciphertexts sent back to it are validly padded.
+pub struct CbcPaddingOracle {
+    key: Vec<u8>,
+}
+
+impl CbcPaddingOracle {
+    pub fn new<R: RngCore>(rng: &mut R) -> Self {
+        Self {
+            key: rng.bytes(BLOCK_SIZE),
+        }
+    }
+
+    /// Encrypt the plaintext under a random IV, returning the IV and the ciphertext.
+    pub fn encrypt<R: RngCore>(&self, rng: &mut R, plaintext: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let iv = rng.bytes(BLOCK_SIZE);
+        let ciphertext =
+            aes::cbc_encrypt(&self.key, &iv, plaintext).expect("Key and IV are one block");
+
+        (iv, ciphertext)
+    }
+}
+
+impl PaddingOracle for CbcPaddingOracle {
+    fn check(&self, iv: &[u8], ct: &[u8]) -> bool {
+        aes::cbc_decrypt(&self.key, iv, ct).is_ok()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaddingOracleError {
+    /// The IV wasn't one block, or the ciphertext wasn't a whole number of blocks.
+    InvalidLength,
+    /// No guess produced valid padding at this byte of this block.
+    NoValidPadding { block: usize, byte: usize },
+    /// The recovered plaintext wasn't validly padded.
+    Padding(PaddingError),
+}
+
+impl fmt::Display for PaddingOracleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLength => write!(f, "IV or ciphertext has an invalid length"),
+            Self::NoValidPadding { block, byte } => {
+                write!(
+                    f,
+                    "no guess gave valid padding at byte {byte} of block {block}"
+                )
+            }
+            Self::Padding(err) => write!(f, "recovered plaintext is badly padded: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for PaddingOracleError {}
+
+/// The result of a padding oracle attack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decrypted {
+    /// The plaintext, with its padding stripped.
+    pub plaintext: Vec<u8>,
+    /// How many times the oracle was asked.
+    pub queries: usize,
+}
+
+/// Recover the block cipher's raw output for one ciphertext block, which XORed with the
+/// previous block gives the plaintext.
+///
+/// Working from the last byte backwards, we tweak the previous block until the oracle accepts
+/// the padding, at which point we know the tweaked byte decrypts to the padding value. We
+/// start from the real previous block, so that the first guess to be accepted for the last
+/// byte may just be the real padding (e.g. `\x02\x02`) rather than `\x01`. To rule that out we
+/// change the second-to-last byte as well and check that the padding is still valid.
+fn decrypt_block<O: PaddingOracle>(
+    oracle: &O,
+    queries: &Cell<usize>,
+    previous: &[u8],
+    block: &[u8],
+    index: usize,
+) -> Result<Vec<u8>, PaddingOracleError> {
+    let check = |iv: &[u8]| {
+        queries.set(queries.get() + 1);
+        oracle.check(iv, block)
+    };
+
+    let mut intermediate = vec![0; BLOCK_SIZE];
+    let mut iv = previous.to_vec();
+
+    for byte in (0..BLOCK_SIZE).rev() {
+        let pad = (BLOCK_SIZE - byte) as u8;
+        for i in byte + 1..BLOCK_SIZE {
+            iv[i] = intermediate[i] ^ pad;
+        }
+
+        let guess = (0x00..=0xFF)
+            .find(|&guess| {
+                iv[byte] = guess;
+                if !check(&iv) {
+                    return false;
+                }
+
+                if byte == BLOCK_SIZE - 1 {
+                    let mut tweaked = iv.clone();
+                    tweaked[byte - 1] ^= 0xFF;
+                    return check(&tweaked);
+                }
+
+                true
+            })
+            .ok_or(PaddingOracleError::NoValidPadding { block: index, byte })?;
+
+        intermediate[byte] = guess ^ pad;
+    }
+
+    Ok(intermediate)
+}
+
+/// Decrypt a CBC ciphertext using nothing but a padding oracle.
+pub fn decrypt<O: PaddingOracle>(
+    oracle: &O,
+    iv: &[u8],
+    ciphertext: &[u8],
+) -> Result<Decrypted, PaddingOracleError> {
+    if iv.len() != BLOCK_SIZE || ciphertext.is_empty() || ciphertext.len() % BLOCK_SIZE != 0 {
+        return Err(PaddingOracleError::InvalidLength);
+    }
+
+    let queries = Cell::new(0);
+    let mut plaintext = Vec::with_capacity(ciphertext.len());
+    let mut previous = iv;
+
+    for (index, block) in ciphertext.chunks(BLOCK_SIZE).enumerate() {
+        let intermediate = decrypt_block(oracle, &queries, previous, block, index)?;
+        plaintext.extend(xor::fixed(&intermediate, previous).expect("Blocks are the same size"));
+        previous = block;
+    }
+
+    let plaintext = pkcs7_unpad(&plaintext, BLOCK_SIZE).map_err(PaddingOracleError::Padding)?;
+
+    Ok(Decrypted {
+        plaintext,
+        queries: queries.get(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    #[test]
+    fn test_oracle_checks_padding() {
+        let mut rng = SplitMix64::new(17);
+        let oracle = CbcPaddingOracle::new(&mut rng);
+        let (iv, ciphertext) = oracle.encrypt(&mut rng, b"YELLOW SUBMARINE");
+
+        assert!(oracle.check(&iv, &ciphertext));
+        assert!(!oracle.check(&iv, &ciphertext[..BLOCK_SIZE]));
+    }
+
+    #[test]
+    fn test_every_padding_length() {
+        let mut rng = SplitMix64::new(17);
+        let oracle = CbcPaddingOracle::new(&mut rng);
+
+        for len in 0..=2 * BLOCK_SIZE {
+            let plaintext = rng.bytes(len);
+            let (iv, ciphertext) = oracle.encrypt(&mut rng, &plaintext);
+
+            assert_eq!(
+                plaintext,
+                decrypt(&oracle, &iv, &ciphertext).unwrap().plaintext
+            );
+        }
+    }
+
+    #[test]
+    fn test_plaintext_ending_like_padding() {
+        // The real padding is `\x02\x02`, which the first guess for the last byte reproduces
+        let mut rng = SplitMix64::new(17);
+        let oracle = CbcPaddingOracle::new(&mut rng);
+        let plaintext = b"fourteen bytes";
+        let (iv, ciphertext) = oracle.encrypt(&mut rng, plaintext);
+
+        assert_eq!(
+            plaintext.to_vec(),
+            decrypt(&oracle, &iv, &ciphertext).unwrap().plaintext
+        );
+    }
+
+    #[test]
+    fn test_rejects_bad_lengths() {
+        let oracle = CbcPaddingOracle::new(&mut SplitMix64::new(17));
+
+        assert_eq!(
+            Err(PaddingOracleError::InvalidLength),
+            decrypt(&oracle, &[0; BLOCK_SIZE], &[0; 17])
+        );
+    }
+}
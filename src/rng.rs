@@ -0,0 +1,389 @@
+use std::ops::Range;
+
+/// A source of random numbers. Oracles take one of these so that tests can make them
+/// deterministic by passing a seeded generator.
+pub trait RngCore {
+    fn next_u32(&mut self) -> u32;
+
+    fn next_u64(&mut self) -> u64 {
+        u64::from(self.next_u32()) << 32 | u64::from(self.next_u32())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let bytes = self.next_u32().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    /// A fresh vector of `len` random bytes.
+    fn bytes(&mut self, len: usize) -> Vec<u8> {
+        let mut bytes = vec![0; len];
+        self.fill_bytes(&mut bytes);
+        bytes
+    }
+
+    /// A random number in the given range. The modulo bias is negligible for the small ranges
+    /// we need.
+    ///
+    /// # Panics
+    ///
+    /// If the range is empty.
+    fn gen_range(&mut self, range: Range<usize>) -> usize {
+        assert!(!range.is_empty(), "Cannot pick from an empty range");
+
+        range.start + (self.next_u64() % (range.end - range.start) as u64) as usize
+    }
+
+    fn gen_bool(&mut self) -> bool {
+        self.next_u32() & 1 == 1
+    }
+}
+
+/// The SplitMix64 generator: tiny, fast and good enough for picking keys and coin flips in
+/// tests.
+#[derive(Debug, Clone)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+}
+
+impl RngCore for SplitMix64 {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// The number of words of MT19937 state.
+pub const N: usize = 624;
+const M: usize = 397;
+const MATRIX_A: u32 = 0x9908b0df;
+const UPPER_MASK: u32 = 0x80000000;
+const LOWER_MASK: u32 = 0x7fffffff;
+
+/// The 32-bit Mersenne Twister, MT19937, following the 2002 reference implementation by
+/// Matsumoto and Nishimura.
+#[derive(Debug, Clone)]
+pub struct Mt19937 {
+    state: [u32; N],
+    index: usize,
+}
+
+impl Mt19937 {
+    /// Seed the generator, as `init_genrand` does.
+    #[must_use]
+    pub fn new(seed: u32) -> Self {
+        let mut state = [0; N];
+        state[0] = seed;
+        for i in 1..N {
+            state[i] = 1812433253u32
+                .wrapping_mul(state[i - 1] ^ (state[i - 1] >> 30))
+                .wrapping_add(i as u32);
+        }
+
+        Self { state, index: N }
+    }
+
+    /// Seed the generator from an array of words, as `init_by_array` does.
+    ///
+    /// # Panics
+    ///
+    /// If the key is empty.
+    #[must_use]
+    pub fn from_array(key: &[u32]) -> Self {
+        assert!(!key.is_empty(), "Need at least one word to seed from");
+
+        let mut mt = Self::new(19650218);
+        let state = &mut mt.state;
+
+        let mut i = 1;
+        let mut j = 0;
+        for _ in 0..N.max(key.len()) {
+            state[i] = (state[i] ^ (state[i - 1] ^ (state[i - 1] >> 30)).wrapping_mul(1664525))
+                .wrapping_add(key[j])
+                .wrapping_add(j as u32);
+            i += 1;
+            j += 1;
+            if i >= N {
+                state[0] = state[N - 1];
+                i = 1;
+            }
+            if j >= key.len() {
+                j = 0;
+            }
+        }
+        for _ in 0..N - 1 {
+            state[i] = (state[i] ^ (state[i - 1] ^ (state[i - 1] >> 30)).wrapping_mul(1566083941))
+                .wrapping_sub(i as u32);
+            i += 1;
+            if i >= N {
+                state[0] = state[N - 1];
+                i = 1;
+            }
+        }
+        state[0] = UPPER_MASK;
+
+        mt
+    }
+
+    /// A generator which will twist the given state before its next output.
+    #[must_use]
+    pub fn from_state(state: [u32; N]) -> Self {
+        Self { state, index: N }
+    }
+
+    /// Generate the next `N` words of state at once.
+    fn twist(&mut self) {
+        for i in 0..N {
+            let y = (self.state[i] & UPPER_MASK) | (self.state[(i + 1) % N] & LOWER_MASK);
+            let mag = if y & 1 == 1 { MATRIX_A } else { 0 };
+            self.state[i] = self.state[(i + M) % N] ^ (y >> 1) ^ mag;
+        }
+
+        self.index = 0;
+    }
+}
+
+/// Scramble a word of MT19937 state into an output, to improve its equidistribution.
+#[must_use]
+pub fn temper(mut y: u32) -> u32 {
+    y ^= y >> 11;
+    y ^= (y << 7) & 0x9d2c5680;
+    y ^= (y << 15) & 0xefc60000;
+    y ^ (y >> 18)
+}
+
+impl RngCore for Mt19937 {
+    fn next_u32(&mut self) -> u32 {
+        if self.index >= N {
+            self.twist();
+        }
+
+        let y = self.state[self.index];
+        self.index += 1;
+
+        temper(y)
+    }
+}
+
+const NN: usize = 312;
+const MM: usize = 156;
+const MATRIX_A_64: u64 = 0xb5026f5aa96619e9;
+const UPPER_MASK_64: u64 = 0xffffffff80000000;
+const LOWER_MASK_64: u64 = 0x7fffffff;
+
+/// The 64-bit Mersenne Twister, MT19937-64, following the 2004 reference implementation by
+/// Nishimura and Matsumoto.
+#[derive(Debug, Clone)]
+pub struct Mt19937_64 {
+    state: [u64; NN],
+    index: usize,
+}
+
+impl Mt19937_64 {
+    /// Seed the generator, as `init_genrand64` does.
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        let mut state = [0; NN];
+        state[0] = seed;
+        for i in 1..NN {
+            state[i] = 6364136223846793005u64
+                .wrapping_mul(state[i - 1] ^ (state[i - 1] >> 62))
+                .wrapping_add(i as u64);
+        }
+
+        Self { state, index: NN }
+    }
+
+    /// Seed the generator from an array of words, as `init_by_array64` does.
+    ///
+    /// # Panics
+    ///
+    /// If the key is empty.
+    #[must_use]
+    pub fn from_array(key: &[u64]) -> Self {
+        assert!(!key.is_empty(), "Need at least one word to seed from");
+
+        let mut mt = Self::new(19650218);
+        let state = &mut mt.state;
+
+        let mut i = 1;
+        let mut j = 0;
+        for _ in 0..NN.max(key.len()) {
+            state[i] = (state[i]
+                ^ (state[i - 1] ^ (state[i - 1] >> 62)).wrapping_mul(3935559000370003845))
+            .wrapping_add(key[j])
+            .wrapping_add(j as u64);
+            i += 1;
+            j += 1;
+            if i >= NN {
+                state[0] = state[NN - 1];
+                i = 1;
+            }
+            if j >= key.len() {
+                j = 0;
+            }
+        }
+        for _ in 0..NN - 1 {
+            state[i] = (state[i]
+                ^ (state[i - 1] ^ (state[i - 1] >> 62)).wrapping_mul(2862933555777941757))
+            .wrapping_sub(i as u64);
+            i += 1;
+            if i >= NN {
+                state[0] = state[NN - 1];
+                i = 1;
+            }
+        }
+        state[0] = 1 << 63;
+
+        mt
+    }
+
+    fn twist(&mut self) {
+        for i in 0..NN {
+            let x = (self.state[i] & UPPER_MASK_64) | (self.state[(i + 1) % NN] & LOWER_MASK_64);
+            let mag = if x & 1 == 1 { MATRIX_A_64 } else { 0 };
+            self.state[i] = self.state[(i + MM) % NN] ^ (x >> 1) ^ mag;
+        }
+
+        self.index = 0;
+    }
+}
+
+impl RngCore for Mt19937_64 {
+    /// The upper half of the next 64-bit output.
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        if self.index >= NN {
+            self.twist();
+        }
+
+        let mut x = self.state[self.index];
+        self.index += 1;
+
+        x ^= (x >> 29) & 0x5555555555555555;
+        x ^= (x << 17) & 0x71d67fffeda60000;
+        x ^= (x << 37) & 0xfff7eee000000000;
+        x ^ (x >> 43)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_mix_64() {
+        // Reference outputs from the C implementation by Sebastiano Vigna
+        let mut rng = SplitMix64::new(1234567);
+
+        assert_eq!(6457827717110365317, rng.next_u64());
+        assert_eq!(3203168211198807973, rng.next_u64());
+        assert_eq!(9817491932198370423, rng.next_u64());
+    }
+
+    #[test]
+    fn test_gen_range() {
+        let mut rng = SplitMix64::new(0);
+
+        for _ in 0..1000 {
+            assert!((5..11).contains(&rng.gen_range(5..11)));
+        }
+    }
+
+    #[test]
+    fn test_fill_bytes_handles_partial_words() {
+        let mut rng = SplitMix64::new(0);
+
+        assert_eq!(7, rng.bytes(7).len());
+    }
+
+    #[test]
+    fn test_mt19937_default_seed() {
+        let mut mt = Mt19937::new(5489);
+
+        assert_eq!(
+            [3499211612, 581869302, 3890346734, 3586334585, 545404204],
+            [(); 5].map(|_| mt.next_u32())
+        );
+    }
+
+    #[test]
+    fn test_mt19937_10000th_output() {
+        // The C++ standard requires this of `std::mt19937`
+        let mut mt = Mt19937::new(5489);
+
+        assert_eq!(
+            4123659995,
+            (0..10000).map(|_| mt.next_u32()).last().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_mt19937_from_array() {
+        // The first outputs in mt19937ar.out
+        let mut mt = Mt19937::from_array(&[0x123, 0x234, 0x345, 0x456]);
+
+        assert_eq!(
+            [1067595299, 955945823, 477289528, 4107218783, 4228976476],
+            [(); 5].map(|_| mt.next_u32())
+        );
+    }
+
+    #[test]
+    fn test_mt19937_64_10000th_output() {
+        // The C++ standard requires this of `std::mt19937_64`
+        let mut mt = Mt19937_64::new(5489);
+
+        assert_eq!(
+            9981545732273789042,
+            (0..10000).map(|_| mt.next_u64()).last().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_mt19937_64_from_array() {
+        // The first outputs in mt19937-64.out
+        let mut mt = Mt19937_64::from_array(&[0x12345, 0x23456, 0x34567, 0x45678]);
+
+        assert_eq!(
+            [
+                7266447313870364031,
+                4946485549665804864,
+                16945909448695747420,
+                16394063075524226720,
+                4873882236456199058
+            ],
+            [(); 5].map(|_| mt.next_u64())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one word")]
+    fn test_mt19937_from_empty_array() {
+        let _ = Mt19937::from_array(&[]);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one word")]
+    fn test_mt19937_64_from_empty_array() {
+        let _ = Mt19937_64::from_array(&[]);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::merkle_damgard::{self, ByteOrder, Hasher, MerkleDamgard};
+
+/// The size of the blocks SHA-1 compresses, in bytes.
+pub const BLOCK_SIZE: usize = merkle_damgard::BLOCK_SIZE;
+
+/// The size of a SHA-1 digest, in bytes.
+pub const DIGEST_SIZE: usize = 20;
+
+const INITIAL_STATE: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+
+/// A SHA-1 hasher, written from FIPS 180-4. Its state (the five registers and the number of
+/// bytes hashed so far) can be read out and resumed from.
+#[derive(Debug, Clone)]
+pub struct Sha1 {
+    hasher: Hasher<5>,
+}
+
+impl Default for Sha1 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sha1 {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from_state(INITIAL_STATE, 0)
+    }
+
+    /// Resume hashing from a state, as if `length` bytes had already been hashed to get it.
+    ///
+    /// # Panics
+    ///
+    /// If `length` isn't a whole number of blocks, since the registers only change at the end
+    /// of a block.
+    #[must_use]
+    pub fn from_state(state: [u32; 5], length: u64) -> Self {
+        let hasher = Hasher::from_state(state, length, ByteOrder::BigEndian, compress)
+            .expect("SHA-1 can only resume at a block boundary");
+
+        Self { hasher }
+    }
+
+    /// The five registers, as they stand after the last complete block.
+    #[must_use]
+    pub fn state(&self) -> [u32; 5] {
+        self.hasher.state()
+    }
+
+    /// The number of bytes hashed so far.
+    #[must_use]
+    pub fn length(&self) -> u64 {
+        self.hasher.length()
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+    }
+
+    #[must_use]
+    pub fn finalize(self) -> [u8; DIGEST_SIZE] {
+        self.hasher
+            .finalize()
+            .try_into()
+            .expect("The registers fill the digest")
+    }
+}
+
+fn compress(state: &mut [u32; 5], block: &[u8]) {
+    let mut w = [0u32; 80];
+    for (word, chunk) in w.iter_mut().zip(block.chunks(4)) {
+        *word = u32::from_be_bytes(chunk.try_into().expect("Chunks are four bytes"));
+    }
+    for t in 16..80 {
+        w[t] = (w[t - 3] ^ w[t - 8] ^ w[t - 14] ^ w[t - 16]).rotate_left(1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e] = *state;
+    for (t, word) in w.into_iter().enumerate() {
+        let (f, k) = match t {
+            0..=19 => ((b & c) | (!b & d), 0x5a827999),
+            20..=39 => (b ^ c ^ d, 0x6ed9eba1),
+            40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+            _ => (b ^ c ^ d, 0xca62c1d6),
+        };
+
+        let temp = a
+            .rotate_left(5)
+            .wrapping_add(f)
+            .wrapping_add(e)
+            .wrapping_add(k)
+            .wrapping_add(word);
+        e = d;
+        d = c;
+        c = b.rotate_left(30);
+        b = a;
+        a = temp;
+    }
+
+    for (h, v) in state.iter_mut().zip([a, b, c, d, e]) {
+        *h = h.wrapping_add(v);
+    }
+}
+
+/// The padding SHA-1 appends to a message of `length` bytes, with the length big-endian.
+#[must_use]
+pub fn padding(length: u64) -> Vec<u8> {
+    merkle_damgard::padding(length, ByteOrder::BigEndian)
+}
+
+impl MerkleDamgard for Sha1 {
+    fn new() -> Self {
+        Self::new()
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.update(data);
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        self.finalize().to_vec()
+    }
+
+    fn padding(length: u64) -> Vec<u8> {
+        padding(length)
+    }
+
+    fn from_digest(digest: &[u8], length: u64) -> Option<Self> {
+        let state = merkle_damgard::state_from_digest(digest, ByteOrder::BigEndian)?;
+        let hasher = Hasher::from_state(state, length, ByteOrder::BigEndian, compress)?;
+
+        Some(Self { hasher })
+    }
+}
+
+#[must_use]
+pub fn sha1(data: &[u8]) -> [u8; DIGEST_SIZE] {
+    let mut hasher = Sha1::new();
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// Authenticate a message by hashing it behind a secret key. This is broken by length
+/// extension, see challenge 29.
+#[must_use]
+pub fn secret_prefix_mac(key: &[u8], message: &[u8]) -> [u8; DIGEST_SIZE] {
+    let mut hasher = Sha1::new();
+    hasher.update(key);
+    hasher.update(message);
+    hasher.finalize()
+}
+
+#[must_use]
+pub fn verify_secret_prefix_mac(key: &[u8], message: &[u8], mac: &[u8]) -> bool {
+    secret_prefix_mac(key, message) == mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex;
+    use test_case::test_case;
+
+    // FIPS 180 examples, plus the empty string
+    #[test_case(b"", "da39a3ee5e6b4b0d3255bfef95601890afd80709"; "empty")]
+    #[test_case(b"abc", "a9993e364706816aba3e25717850c26c9cd0d89d"; "one block")]
+    #[test_case(
+        b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+        "84983e441c3bd26ebaae4aa1f95129e5e54670f1";
+        "two blocks"
+    )]
+    fn test_sha1(input: &[u8], expected: &str) {
+        assert_eq!(expected, hex::encode(&sha1(input)));
+    }
+
+    #[test]
+    fn test_sha1_million_a() {
+        let mut hasher = Sha1::new();
+        for _ in 0..1000 {
+            hasher.update(&[b'a'; 1000]);
+        }
+
+        assert_eq!(
+            "34aa973cd4c4daa4f61eeb2bdbad27316534016f",
+            hex::encode(&hasher.finalize())
+        );
+    }
+
+    #[test]
+    fn test_update_in_pieces() {
+        let message = b"The quick brown fox jumps over the lazy dog, again and again and again";
+        let mut hasher = Sha1::new();
+        for chunk in message.chunks(7) {
+            hasher.update(chunk);
+        }
+
+        assert_eq!(sha1(message), hasher.finalize());
+    }
+
+    #[test]
+    fn test_resume_from_state() {
+        let message = [b'x'; 3 * BLOCK_SIZE];
+        let mut hasher = Sha1::new();
+        hasher.update(&message[..2 * BLOCK_SIZE]);
+
+        let mut resumed = Sha1::from_state(hasher.state(), hasher.length());
+        resumed.update(&message[2 * BLOCK_SIZE..]);
+
+        assert_eq!(sha1(&message), resumed.finalize());
+    }
+
+    #[test]
+    fn test_padding_length() {
+        for length in 0..200 {
+            assert_eq!(
+                0,
+                (length + padding(length).len() as u64) % BLOCK_SIZE as u64
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_digest_rejects_bad_input() {
+        let digest = [0; DIGEST_SIZE];
+
+        assert!(<Sha1 as MerkleDamgard>::from_digest(&digest[1..], 0).is_none());
+        assert!(<Sha1 as MerkleDamgard>::from_digest(&digest, 5).is_none());
+        assert!(<Sha1 as MerkleDamgard>::from_digest(&digest, BLOCK_SIZE as u64).is_some());
+    }
+
+    #[test]
+    fn test_secret_prefix_mac() {
+        let mac = secret_prefix_mac(b"key", b"message");
+
+        assert!(verify_secret_prefix_mac(b"key", b"message", &mac));
+        assert!(!verify_secret_prefix_mac(b"key", b"massage", &mac));
+        assert!(!verify_secret_prefix_mac(b"kay", b"message", &mac));
+    }
+}
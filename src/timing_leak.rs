@@ -0,0 +1,512 @@
+use std::{
+    cmp::Reverse,
+    io::{self, BufRead, BufReader, Write},
+    net::{Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream},
+    ops::ControlFlow,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{cookie::Cookie, hex, hmac::hmac_sha1};
+
+/// The open connections of a `Server`, with the threads serving them.
+type Connections = Arc<Mutex<Vec<(TcpStream, JoinHandle<()>)>>>;
+
+/// Compare two byte strings the slow way: one byte at a time, sleeping for `delay` after each
+/// matching byte and bailing out at the first mismatch. How long it takes gives away how much
+/// of `a` is a prefix of `b`.
+pub fn insecure_compare(a: &[u8], b: &[u8], delay: Duration) -> bool {
+    for (x, y) in a.iter().zip(b) {
+        if x != y {
+            return false;
+        }
+        thread::sleep(delay);
+    }
+
+    a.len() == b.len()
+}
+
+/// A web server on 127.0.0.1 with a single endpoint, `/test?file=...&signature=...`, which
+/// answers 200 if `signature` is the hex HMAC-SHA1 of `file` under a secret key and 500 if not.
+/// The signature is checked with `insecure_compare`. The server shuts down when dropped, and
+/// hangs up on any clients still connected.
+pub struct Server {
+    addr: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+    connections: Connections,
+}
+
+impl Server {
+    /// Start listening on a free port, handling each connection on its own thread.
+    ///
+    /// # Errors
+    ///
+    /// If the listening socket can't be set up.
+    pub fn start(key: &[u8], delay: Duration) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let addr = listener.local_addr()?;
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let key: Arc<[u8]> = key.into();
+        let connections = Connections::default();
+
+        let handle = thread::spawn({
+            let shutdown = Arc::clone(&shutdown);
+            let connections = Arc::clone(&connections);
+            move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let Ok(stream) = stream else {
+                        continue;
+                    };
+                    // Kept to hang up on the client when shutting down
+                    let Ok(clone) = stream.try_clone() else {
+                        continue;
+                    };
+
+                    let key = Arc::clone(&key);
+                    let handle = thread::spawn(move || {
+                        // A client hanging up early isn't the server's problem
+                        serve(stream, &key, delay).ok();
+                    });
+
+                    let mut connections = connections.lock().expect("Lock poisoned");
+                    connections.retain(|(_, handle)| !handle.is_finished());
+                    connections.push((clone, handle));
+                }
+            }
+        });
+
+        Ok(Self {
+            addr,
+            shutdown,
+            handle: Some(handle),
+            connections,
+        })
+    }
+
+    #[must_use]
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        // Wake the accept loop up so it sees the flag. If that fails it's stuck waiting for a
+        // connection, and joining it would hang.
+        if let Some(handle) = self.handle.take() {
+            if TcpStream::connect(self.addr).is_ok() {
+                handle.join().ok();
+            }
+        }
+
+        // Hang up on clients that are still connected, so their threads stop waiting for
+        // another request
+        let connections = std::mem::take(&mut *self.connections.lock().expect("Lock poisoned"));
+        for (stream, handle) in connections {
+            stream.shutdown(Shutdown::Both).ok();
+            handle.join().ok();
+        }
+    }
+}
+
+fn serve(stream: TcpStream, key: &[u8], delay: Duration) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+    let mut reader = BufReader::new(&stream);
+
+    // Keep answering requests until the client hangs up
+    loop {
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line)? == 0 {
+            return Ok(());
+        }
+        skip_headers(&mut reader)?;
+
+        let status = respond(&request_line, key, delay);
+        let response = format!("HTTP/1.1 {status}\r\nContent-Length: 0\r\n\r\n");
+        (&stream).write_all(response.as_bytes())?;
+    }
+}
+
+fn respond(request_line: &str, key: &[u8], delay: Duration) -> &'static str {
+    match request_line.split_whitespace().collect::<Vec<_>>()[..] {
+        ["GET", target, _] => match target.split_once('?') {
+            Some(("/test", query)) => {
+                let query = Cookie::parse(query);
+                match (query.get("file"), query.get("signature")) {
+                    (Some(file), Some(signature)) if is_hex(signature) => {
+                        let mac = hmac_sha1(key, file.as_bytes());
+                        if insecure_compare(&mac, &hex::decode(signature), delay) {
+                            "200 OK"
+                        } else {
+                            "500 Internal Server Error"
+                        }
+                    }
+                    _ => "400 Bad Request",
+                }
+            }
+            _ => "404 Not Found",
+        },
+        _ => "400 Bad Request",
+    }
+}
+
+fn is_hex(s: &str) -> bool {
+    s.len() % 2 == 0 && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Read up to the blank line that ends the headers. We don't need any of them.
+fn skip_headers<R: BufRead>(reader: &mut R) -> io::Result<()> {
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim_end().is_empty() {
+        header.clear();
+    }
+
+    Ok(())
+}
+
+/// A client for `Server` which keeps its connections open between requests, so that a request
+/// costs little more than the server's own work. It can be shared between threads, which each
+/// take an idle connection or open a new one.
+pub struct Client {
+    addr: SocketAddr,
+    idle: Mutex<Vec<BufReader<TcpStream>>>,
+}
+
+impl Client {
+    #[must_use]
+    pub fn new(addr: SocketAddr) -> Self {
+        Self {
+            addr,
+            idle: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Ask the server whether `signature` is valid for `file`. `file` is sent as is, so it
+    /// mustn't contain anything that needs URL encoding.
+    ///
+    /// # Errors
+    ///
+    /// If the request fails or the response doesn't make sense.
+    pub fn check_signature(&self, file: &str, signature: &[u8]) -> io::Result<bool> {
+        let target = format!("/test?file={file}&signature={}", hex::encode(signature));
+        Ok(self.get(&target)? == 200)
+    }
+
+    /// Send a GET for `target` and return the response's status code.
+    fn get(&self, target: &str) -> io::Result<u16> {
+        let idle = self.idle.lock().expect("Lock poisoned").pop();
+        let mut connection = match idle {
+            Some(connection) => connection,
+            None => {
+                let stream = TcpStream::connect(self.addr)?;
+                stream.set_nodelay(true)?;
+                BufReader::new(stream)
+            }
+        };
+
+        let request = format!("GET {target} HTTP/1.1\r\nHost: {}\r\n\r\n", self.addr);
+        connection.get_mut().write_all(request.as_bytes())?;
+
+        let mut status_line = String::new();
+        connection.read_line(&mut status_line)?;
+        skip_headers(&mut connection)?;
+        let status = status_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse().ok())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Malformed status line"))?;
+
+        self.idle.lock().expect("Lock poisoned").push(connection);
+        Ok(status)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recovered {
+    pub signature: Vec<u8>,
+    /// How many times `check` was called.
+    pub queries: usize,
+}
+
+/// The most rounds of timing spent on one byte.
+const ROUNDS: usize = 6;
+
+/// Recover a `len`-byte signature from how long `check` takes to reject guesses, one byte at a
+/// time and up to `threads` guesses at once. `None` if noise keeps leading it astray.
+///
+/// # Panics
+///
+/// If `len` or `threads` is zero.
+pub fn recover_signature<F>(len: usize, threads: usize, check: F) -> Option<Recovered>
+where
+    F: Fn(&[u8]) -> bool + Sync,
+{
+    assert!(len > 0, "Need at least one byte of signature to recover");
+    assert!(threads > 0, "Need at least one thread to guess with");
+
+    let mut timer = Timer {
+        check,
+        len,
+        interval: Duration::ZERO,
+        threads,
+        queries: AtomicUsize::new(0),
+    };
+    timer.calibrate();
+
+    let mut steps: Vec<Step> = Vec::with_capacity(len);
+    let mut retries = 0;
+
+    while steps.len() < len {
+        let known: Vec<_> = steps.iter().map(|step| step.byte).collect();
+        // Each right byte adds a delay to every guess after it
+        let delay = match &steps[..] {
+            [] => None,
+            [only] => Some(only.chosen.saturating_sub(only.median)),
+            [first, .., last] => {
+                Some(last.median.saturating_sub(first.median) / (steps.len() as u64 - 1))
+            }
+        };
+
+        let step = match timer.step(&known, delay) {
+            ControlFlow::Continue(step) => step,
+            // The last byte has nothing after it to time, so it's only found this way
+            ControlFlow::Break(signature) => {
+                return Some(Recovered {
+                    signature,
+                    queries: timer.queries.into_inner(),
+                })
+            }
+        };
+
+        // If this byte's guesses aren't slower than the last byte's, the last byte was wrong; if
+        // no candidate stood out, this one is. Either way, back up or try again.
+        if let (Some(last), Some(delay)) = (steps.last(), delay) {
+            let last_was_wrong = step.median < last.median + delay / 2;
+            if last_was_wrong || step.chosen < step.median + delay / 2 {
+                retries += 1;
+                if retries > 4 * len {
+                    return None;
+                }
+                if last_was_wrong {
+                    steps.pop();
+                }
+                continue;
+            }
+        }
+        steps.push(step);
+    }
+
+    // Every byte was chosen but the whole signature was never accepted
+    None
+}
+
+/// What timing the candidates for one byte showed, in nanoseconds.
+struct Step {
+    byte: u8,
+    /// How long rejecting a typical candidate took, which is how long a wrong byte here takes.
+    median: u64,
+    /// The fastest time for the chosen byte, which stood out as the slowest of the candidates.
+    chosen: u64,
+}
+
+struct Timer<F> {
+    check: F,
+    len: usize,
+    /// How long to wait between starting guesses.
+    interval: Duration,
+    /// How many guesses can be in flight at once.
+    threads: usize,
+    queries: AtomicUsize,
+}
+
+impl<F: Fn(&[u8]) -> bool + Sync> Timer<F> {
+    /// Measure how long `check` takes to reject a guess that's wrong from the first byte (one
+    /// of these sixteen guesses at most isn't), and pace guesses at four times that, which leaves
+    /// the CPU enough slack that guesses don't hold each other up.
+    fn calibrate(&mut self) {
+        let quickest = (0..16)
+            .map(|first| {
+                let mut guess = vec![0; self.len];
+                guess[0] = first;
+                self.queries.fetch_add(1, Ordering::Relaxed);
+
+                let start = Instant::now();
+                (self.check)(&guess);
+                start.elapsed()
+            })
+            .min()
+            .expect("There are sixteen guesses");
+
+        self.interval = 4 * quickest;
+    }
+
+    /// Pick the byte after `known`, or stop with a signature if a guess was accepted.
+    fn step(&self, known: &[u8], delay: Option<u64>) -> ControlFlow<Vec<u8>, Step> {
+        let fastest: Vec<_> = (0..256).map(|_| AtomicU64::new(u64::MAX)).collect();
+        let load = |candidate: u8| fastest[candidate as usize].load(Ordering::Relaxed);
+
+        // Each round times every candidate still in the running once more, and judges it by its
+        // fastest attempt: noise only ever adds time, so a wrong candidate only has to be timed
+        // cleanly once to show itself
+        let mut candidates: Vec<u8> = (0..=255).collect();
+        let mut median = None;
+        for _ in 0..ROUNDS {
+            if let Some(signature) = self.time(known, candidates.iter().copied(), &fastest) {
+                return ControlFlow::Break(signature);
+            }
+            candidates.sort_by_key(|&candidate| Reverse(load(candidate)));
+
+            // The first round has the most candidates, nearly all of them wrong
+            let median = *median.get_or_insert_with(|| load(candidates[candidates.len() / 2]));
+            // The right byte should be a whole delay slower than the rest. Until we know the
+            // delay, the slowest quarter go through.
+            let keep = match delay {
+                Some(delay) => candidates
+                    .iter()
+                    .take_while(|&&candidate| load(candidate) > median + delay / 2)
+                    .count(),
+                None => candidates.len() / 4,
+            };
+
+            // If nothing stood out, keep the best anyway and let the caller judge it
+            candidates.truncate(keep.max(1));
+            if keep <= 1 {
+                break;
+            }
+        }
+
+        let byte = candidates[0];
+        ControlFlow::Continue(Step {
+            byte,
+            median: median.expect("There was at least one round"),
+            chosen: load(byte),
+        })
+    }
+
+    /// Time a guess for each of `candidates` as the byte after `known`, folding the times into
+    /// `fastest`. Returns a guess that was accepted, if there was one.
+    fn time(
+        &self,
+        known: &[u8],
+        candidates: impl Iterator<Item = u8> + Send,
+        fastest: &[AtomicU64],
+    ) -> Option<Vec<u8>> {
+        let candidates = Mutex::new(candidates.enumerate());
+        let accepted = OnceLock::new();
+        let start = Instant::now();
+
+        thread::scope(|scope| {
+            for _ in 0..self.threads {
+                scope.spawn(|| {
+                    let mut guess = known.to_vec();
+                    guess.resize(self.len, 0);
+
+                    loop {
+                        let next = candidates.lock().expect("Lock poisoned").next();
+                        let Some((n, candidate)) = next else {
+                            break;
+                        };
+                        if accepted.get().is_some() {
+                            break;
+                        }
+                        // Start guesses at a steady pace, so that their sleeps overlap without
+                        // them all fighting over the CPU at once
+                        if let Some(wait) = (start + n as u32 * self.interval)
+                            .checked_duration_since(Instant::now())
+                        {
+                            thread::sleep(wait);
+                        }
+
+                        guess[known.len()] = candidate;
+                        self.queries.fetch_add(1, Ordering::Relaxed);
+
+                        let start = Instant::now();
+                        let ok = (self.check)(&guess);
+                        let elapsed = start.elapsed().as_nanos() as u64;
+
+                        if ok {
+                            accepted.set(guess.clone()).ok();
+                        }
+                        fastest[candidate as usize].fetch_min(elapsed, Ordering::Relaxed);
+                    }
+                });
+            }
+        });
+
+        accepted.into_inner()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insecure_compare() {
+        let delay = Duration::from_millis(5);
+
+        assert!(insecure_compare(b"abc", b"abc", delay));
+        assert!(!insecure_compare(b"abc", b"abd", delay));
+        assert!(!insecure_compare(b"abc", b"ab", delay));
+
+        let start = Instant::now();
+        insecure_compare(b"abcd", b"abxx", delay);
+        assert!(start.elapsed() >= 2 * delay);
+    }
+
+    #[test]
+    fn test_server() {
+        let server = Server::start(b"key", Duration::ZERO).unwrap();
+        let client = Client::new(server.addr());
+        let mac = hmac_sha1(b"key", b"foo");
+
+        assert!(client.check_signature("foo", &mac).unwrap());
+        assert!(!client.check_signature("bar", &mac).unwrap());
+        assert!(!client.check_signature("foo", &mac[..19]).unwrap());
+        assert_eq!(400, client.get("/test?file=foo&signature=xyz").unwrap());
+        assert_eq!(400, client.get("/test?file=foo").unwrap());
+        assert_eq!(404, client.get("/other?file=foo").unwrap());
+    }
+
+    #[test]
+    fn test_server_hangs_up_on_drop() {
+        let server = Server::start(b"key", Duration::ZERO).unwrap();
+        let client = Client::new(server.addr());
+        client.check_signature("foo", b"").unwrap();
+
+        drop(server);
+
+        assert!(client.check_signature("foo", b"").is_err());
+    }
+
+    #[test]
+    fn test_recover_signature() {
+        let secret = [3, 200, 17];
+        let recovered = recover_signature(3, 16, |guess| {
+            insecure_compare(&secret, guess, Duration::from_millis(2))
+        })
+        .unwrap();
+
+        assert_eq!(secret.to_vec(), recovered.signature);
+    }
+
+    #[test]
+    fn test_recover_signature_without_a_leak() {
+        assert_eq!(None, recover_signature(2, 4, |_| false));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one byte")]
+    fn test_recover_signature_of_no_bytes() {
+        let _ = recover_signature(0, 4, |_| false);
+    }
+}
@@ -1,7 +1,29 @@
+use cryptopals::aes;
 use cryptopals::base64;
+use cryptopals::bigint::BigUint;
+use cryptopals::bitflip::{self, CbcOracle, CtrOracle};
+use cryptopals::byte_at_a_time::{self, EcbOracle};
+use cryptopals::cookie::{self, ProfileOracle};
+use cryptopals::crib::CribSession;
+use cryptopals::ctr_edit::{self, EditOracle};
+use cryptopals::dh::{self, Group};
+use cryptopals::fixed_nonce::{self, Lengths};
 use cryptopals::hex;
+use cryptopals::hmac;
+use cryptopals::key_as_iv::{self, KeyAsIvOracle};
+use cryptopals::md4::{self, Md4};
+use cryptopals::merkle_damgard;
+use cryptopals::mode_detection::{self, EncryptionOracle};
+use cryptopals::mt_cipher;
+use cryptopals::mt_crack::{self, Clock, FakeClock};
+use cryptopals::padding;
+use cryptopals::padding_oracle::{self, CbcPaddingOracle};
+use cryptopals::rng::{Mt19937, RngCore, SplitMix64};
+use cryptopals::sha1::{self, Sha1};
 use cryptopals::sorted::Sorted;
+use cryptopals::timing_leak::{self, Client, Server};
 use cryptopals::xor;
+use std::time::Duration;
 
 #[test]
 fn base64_encode() {
@@ -94,3 +116,449 @@ fn break_repeating_key_xor() {
     );
     assert_eq!("I'm back and I'm ringin' the bell \nA rockin' on the mike while the fly girls yell \nIn ecstasy in the back of me \nWell that's my DJ Deshay cuttin' all them Z's \nHittin' hard and the girlies goin' crazy \nVanilla's on the mike, man I'm not lazy. \n\nI'm lettin' my drug kick in \nIt controls my mouth and I begin \nTo just let it flow, let my concepts go \nMy posse's to the side yellin', Go Vanilla Go! \n\nSmooth 'cause that's the way I will be \nAnd if you don't give a damn, then \nWhy you starin' at me \nSo get off 'cause I control the stage \nThere's no dissin' allowed \nI'm in my own phase \nThe girlies sa y they love me and that is ok \nAnd I can dance better than any kid n' play \n\nStage 2 -- Yea the one ya' wanna listen to \nIt's off my head so let the beat play through \nSo I can funk it up and make it sound good \n1-2-3 Yo -- Knock on some wood \nFor good luck, I like my rhymes atrocious \nSupercalafragilisticexpialidocious \nI'm an effect and that you can bet \nI can take a fly girl and make her wet. \n\nI'm like Samson -- Samson to Delilah \nThere's no denyin', You can try to hang \nBut you'll keep tryin' to get my style \nOver and over, practice makes perfect \nBut not if you're a loafer. \n\nYou'll get nowhere, no place, no time, no girls \nSoon -- Oh my God, homebody, you probably eat \nSpaghetti with a spoon! Come on and say it! \n\nVIP. Vanilla Ice yep, yep, I'm comin' hard like a rhino \nIntoxicating so you stagger like a wino \nSo punks stop trying and girl stop cryin' \nVanilla Ice is sellin' and you people are buyin' \n'Cause why the freaks are jockin' like Crazy Glue \nMovin' and groovin' trying to sing along \nAll through the ghetto groovin' this here song \nNow you're amazed by the VIP posse. \n\nSteppin' so hard like a German Nazi \nStartled by the bases hittin' ground \nThere's no trippin' on mine, I'm just gettin' down \nSparkamatic, I'm hangin' tight like a fanatic \nYou trapped me once and I thought that \nYou might have it \nSo step down and lend me your ear \n'89 in my time! You, '90 is my year. \n\nYou're weakenin' fast, YO! and I can tell it \nYour body's gettin' hot, so, so I can smell it \nSo don't be mad and don't be sad \n'Cause the lyrics belong to ICE, You can call me Dad \nYou're pitchin' a fit, so step back and endure \nLet the witch doctor, Ice, do the dance to cure \nSo come up close and don't be square \nYou wanna battle me -- Anytime, anywhere \n\nYou thought that I was weak, Boy, you're dead wrong \nSo come on, everybody and sing this song \n\nSay -- Play that funky music Say, go white boy, go white boy go \nplay that funky music Go white boy, go white boy, go \nLay down and boogie and play that funky music till you die. \n\nPlay that funky music Come on, Come on, let me hear \nPlay that funky music white boy you say it, say it \nPlay that funky music A little louder now \nPlay that funky music, white boy Come on, Come on, Come on \nPlay that funky music \n", &String::from_utf8_lossy(&solution.message));
 }
+
+#[test]
+fn implement_pkcs7_padding() {
+    assert_eq!(
+        b"YELLOW SUBMARINE\x04\x04\x04\x04".as_slice(),
+        padding::pkcs7_pad(b"YELLOW SUBMARINE", 20)
+    );
+}
+
+#[test]
+fn implement_cbc_mode() {
+    let ciphertext = base64::decode(include_str!("../data/10.txt"));
+
+    let plaintext = aes::cbc_decrypt(b"YELLOW SUBMARINE", &[0; 16], &ciphertext).unwrap();
+
+    assert!(plaintext.starts_with(b"I'm back and I'm ringin' the bell \n"));
+    assert!(plaintext.ends_with(b"Play that funky music \n"));
+    assert_eq!(
+        ciphertext,
+        aes::cbc_encrypt(b"YELLOW SUBMARINE", &[0; 16], &plaintext).unwrap()
+    );
+}
+
+#[test]
+fn implement_ctr_the_stream_cipher_mode() {
+    let ciphertext =
+        base64::decode("L77na/nrFsKvynd6HzOoG7GHTLXsTVu9qvY/2syLXzhPweyyMTJULu/6/kXX0KSvoOLSFQ==");
+
+    let plaintext = aes::ctr(
+        b"YELLOW SUBMARINE",
+        aes::CtrCounter::LittleEndian { nonce: 0 },
+        &ciphertext,
+    )
+    .unwrap();
+
+    assert_eq!(
+        "Yo, VIP Let's kick it Ice, Ice, baby Ice, Ice, baby ",
+        String::from_utf8_lossy(&plaintext)
+    );
+}
+
+#[test]
+fn detect_aes_in_ecb_mode() {
+    let ciphertexts: Vec<_> = include_str!("../data/8.txt")
+        .lines()
+        .map(|l| hex::decode(l.trim()))
+        .collect();
+
+    let candidates = aes::detect_ecb(&ciphertexts);
+
+    assert_eq!(132, candidates[0].index);
+    assert_eq!(3, candidates[0].repetitions);
+    assert_eq!(vec![vec![16, 48, 80, 112]], candidates[0].duplicates);
+    assert_eq!(0, candidates[1].repetitions);
+}
+
+#[test]
+fn an_ecb_cbc_detection_oracle() {
+    let mut oracle = EncryptionOracle::new(SplitMix64::new(11));
+
+    let guesses: Vec<_> = (0..1000)
+        .map(|_| mode_detection::detect_mode(|input| oracle.encrypt(input)))
+        .collect();
+
+    assert_eq!(oracle.history(), guesses.as_slice());
+    assert!(guesses.contains(&mode_detection::Mode::Ecb));
+    assert!(guesses.contains(&mode_detection::Mode::Cbc));
+}
+
+const CHALLENGE_12_SUFFIX: &str = "Um9sbGluJyBpbiBteSA1LjAKV2l0aCBteSByYWctdG9wIGRvd24gc28gbXkg
+aGFpciBjYW4gYmxvdwpUaGUgZ2lybGllcyBvbiBzdGFuZGJ5IHdhdmluZyBq
+dXN0IHRvIHNheSBoaQpEaWQgeW91IHN0b3A/IE5vLCBJIGp1c3QgZHJvdmUg
+YnkK";
+
+#[test]
+fn byte_at_a_time_ecb_decryption_simple() {
+    let mut rng = SplitMix64::new(12);
+    let oracle = EcbOracle::new(&mut rng, Vec::new(), base64::decode(CHALLENGE_12_SUFFIX));
+
+    let recovered = byte_at_a_time::recover_suffix(|input| oracle.encrypt(input)).unwrap();
+
+    assert_eq!(16, recovered.block_size);
+    assert_eq!(0, recovered.prefix_len);
+    assert_eq!(
+        "Rollin' in my 5.0\nWith my rag-top down so my hair can blow\nThe girlies on standby waving just to say hi\nDid you stop? No, I just drove by\n",
+        String::from_utf8_lossy(&recovered.suffix)
+    );
+    // The suffix is ASCII, so each byte takes one query for the target block and at most 128
+    // guesses, plus a few hundred to size up the oracle
+    assert!(recovered.queries <= 129 * recovered.suffix.len() + 256);
+}
+
+#[test]
+fn byte_at_a_time_ecb_decryption_harder() {
+    let mut rng = SplitMix64::new(14);
+    let oracle = EcbOracle::with_random_prefix(&mut rng, base64::decode(CHALLENGE_12_SUFFIX));
+
+    let recovered = byte_at_a_time::recover_suffix(|input| oracle.encrypt(input)).unwrap();
+
+    assert_eq!(base64::decode(CHALLENGE_12_SUFFIX), recovered.suffix);
+    assert!(recovered.queries <= 129 * recovered.suffix.len() + 256);
+}
+
+#[test]
+fn ecb_cut_and_paste() {
+    let mut rng = SplitMix64::new(13);
+    let oracle = ProfileOracle::new(&mut rng);
+
+    let forged = cookie::forge_admin(|email| oracle.encrypt(email));
+    let profile = oracle.decrypt(&forged).unwrap();
+
+    assert_eq!(Some("admin"), profile.get("role"));
+    assert_eq!(Some("10"), profile.get("uid"));
+}
+
+#[test]
+fn cbc_bitflipping_attacks() {
+    let oracle = CbcOracle::new(&mut SplitMix64::new(16));
+
+    assert_eq!(Ok(false), oracle.is_admin(&oracle.encrypt(b";admin=true;")));
+
+    let forged = bitflip::forge_admin_cbc(|userdata| oracle.encrypt(userdata));
+
+    assert_eq!(Ok(true), oracle.is_admin(&forged));
+}
+
+#[test]
+fn the_cbc_padding_oracle() {
+    let strings = [
+        "MDAwMDAwTm93IHRoYXQgdGhlIHBhcnR5IGlzIGp1bXBpbmc=",
+        "MDAwMDAxV2l0aCB0aGUgYmFzcyBraWNrZWQgaW4gYW5kIHRoZSBWZWdhJ3MgYXJlIHB1bXBpbic=",
+        "MDAwMDAyUXVpY2sgdG8gdGhlIHBvaW50LCB0byB0aGUgcG9pbnQsIG5vIGZha2luZw==",
+        "MDAwMDAzQ29va2luZyBNQydzIGxpa2UgYSBwb3VuZCBvZiBiYWNvbg==",
+        "MDAwMDA0QnVybmluZyAnZW0sIGlmIHlvdSBhaW4ndCBxdWljayBhbmQgbmltYmxl",
+        "MDAwMDA1SSBnbyBjcmF6eSB3aGVuIEkgaGVhciBhIGN5bWJhbCBhbmQ=",
+        "MDAwMDA2QW5kIGEgaGlnaCBoYXQgd2l0aCBhIHNvdXBlZCB1cCB0ZW1wbw==",
+        "MDAwMDA3SSdtIG9uIGEgcm9sbCwgaXQncyB0aW1lIHRvIGdvIHNvbG8=",
+        "MDAwMDA4b2xsaW4nIGluIG15IGZpdmUgcG9pbnQgb2g=",
+        "MDAwMDA5aXRoIG15IHJhZy10b3AgZG93biBzbyBteSBoYWlyIGNhbiBibG93",
+    ];
+    let mut rng = SplitMix64::new(17);
+    let oracle = CbcPaddingOracle::new(&mut rng);
+
+    for string in strings {
+        let plaintext = base64::decode(string);
+        let (iv, ciphertext) = oracle.encrypt(&mut rng, &plaintext);
+
+        let decrypted = padding_oracle::decrypt(&oracle, &iv, &ciphertext).unwrap();
+
+        assert_eq!(plaintext, decrypted.plaintext);
+        // At most 256 guesses per byte, plus a tweaked check after each of the (at most two)
+        // guesses the last byte of a block accepts, to rule out `\x02\x02`
+        assert!(decrypted.queries <= (256 * 16 + 2) * (ciphertext.len() / 16));
+    }
+}
+
+/// The lines of the plaintext of `data/7.txt`, encrypted with CTR under the same key and nonce.
+/// These stand in for the plaintexts of challenges 19 and 20.
+fn fixed_nonce_ciphertexts() -> (Vec<Vec<u8>>, Vec<u8>) {
+    let plaintext = aes::ecb_decrypt_padded(
+        b"YELLOW SUBMARINE",
+        &base64::decode(include_str!("../data/7.txt")),
+    )
+    .unwrap();
+
+    let mut rng = SplitMix64::new(19);
+    let key = rng.bytes(16);
+    let counter = aes::CtrCounter::LittleEndian { nonce: 0 };
+
+    let ciphertexts: Vec<_> = String::from_utf8(plaintext)
+        .unwrap()
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .map(|line| aes::ctr(&key, counter, line.as_bytes()).unwrap())
+        .collect();
+    let longest = ciphertexts.iter().map(Vec::len).max().unwrap();
+
+    (
+        ciphertexts,
+        aes::ctr_keystream(&key, counter, 0, longest).unwrap(),
+    )
+}
+
+#[test]
+fn break_fixed_nonce_ctr_statistically() {
+    let (ciphertexts, keystream) = fixed_nonce_ciphertexts();
+
+    let truncated = fixed_nonce::break_fixed_nonce(&ciphertexts, Lengths::Truncate);
+    let correct = truncated
+        .keystream()
+        .iter()
+        .zip(&keystream)
+        .filter(|(b1, b2)| b1 == b2)
+        .count();
+
+    assert_eq!(17, truncated.columns.len());
+    assert!(correct >= 15, "only {correct} of 17 bytes recovered");
+    assert!(truncated.weak_columns(20).is_empty());
+
+    let ragged = fixed_nonce::break_fixed_nonce(&ciphertexts, Lengths::Ragged);
+    let weak = ragged.weak_columns(20);
+    let strong_correct = ragged
+        .keystream()
+        .iter()
+        .zip(&keystream)
+        .enumerate()
+        .filter(|(i, (b1, b2))| !weak.contains(i) && b1 == b2)
+        .count();
+
+    assert_eq!(keystream.len(), ragged.columns.len());
+    assert_eq!((45..keystream.len()).collect::<Vec<_>>(), weak);
+    assert!(
+        strong_correct >= 40,
+        "only {strong_correct} of 45 bytes recovered"
+    );
+}
+
+#[test]
+fn break_fixed_nonce_ctr_using_substitutions() {
+    let (ciphertexts, keystream) = fixed_nonce_ciphertexts();
+    let mut session = CribSession::new(ciphertexts[..6].to_vec());
+
+    let drag = session.drag(2, b" the ").remove(0);
+
+    assert_eq!(13, drag.offset);
+    assert_eq!(keystream[13..18], drag.keystream);
+
+    session.pin(drag.offset, &drag.keystream);
+    let rendered = session.render('_');
+
+    assert_eq!("_____________I'm r", &rendered[0][..18]);
+    assert_eq!("_____________y DJ ", &rendered[3][..18]);
+
+    session.pin_plaintext(0, 13, b"I'm ringin'");
+    let rendered = session.render('_');
+
+    assert_eq!("_____________y DJ Deshay", &rendered[3][..24]);
+    assert_eq!("_____________the mike,", &rendered[5][..22]);
+}
+
+#[test]
+fn break_random_access_read_write_aes_ctr() {
+    // The input to this challenge is the plaintext of challenge 7
+    let plaintext = aes::ecb_decrypt_padded(
+        b"YELLOW SUBMARINE",
+        &base64::decode(include_str!("../data/7.txt")),
+    )
+    .unwrap();
+    let oracle = EditOracle::new(&mut SplitMix64::new(25));
+    let ciphertext = oracle.encrypt(&plaintext);
+
+    let recovered = ctr_edit::recover_plaintext(&ciphertext, |ciphertext, offset, newtext| {
+        oracle.edit(ciphertext, offset, newtext).unwrap()
+    });
+
+    assert_eq!(plaintext, recovered);
+    assert!(recovered.starts_with(b"I'm back and I'm ringin' the bell"));
+}
+
+#[test]
+fn ctr_bitflipping() {
+    let oracle = CtrOracle::new(&mut SplitMix64::new(26));
+
+    assert_eq!(Ok(false), oracle.is_admin(&oracle.encrypt(b";admin=true;")));
+
+    let forged = bitflip::forge_admin_ctr(|userdata| oracle.encrypt(userdata));
+
+    assert_eq!(Ok(true), oracle.is_admin(&forged));
+}
+
+#[test]
+fn recover_the_key_from_cbc_with_iv_equals_key() {
+    let oracle = KeyAsIvOracle::new(&mut SplitMix64::new(27));
+    let ciphertext = oracle.encrypt(b"some perfectly innocent user data");
+
+    let key = key_as_iv::recover_key(&ciphertext, |c| oracle.receive(c)).unwrap();
+
+    let plaintext = aes::cbc_decrypt_key_as_iv(&key, &ciphertext).unwrap();
+    assert!(plaintext.starts_with(bitflip::PREFIX));
+    assert_eq!(
+        oracle.encrypt(b"chosen by the attacker"),
+        aes::cbc_encrypt_key_as_iv(
+            &key,
+            &[bitflip::PREFIX, b"chosen by the attacker", bitflip::SUFFIX].concat()
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn crack_an_mt19937_seed() {
+    let clock = FakeClock::new(1_700_000_000);
+    let mut rng = SplitMix64::new(22);
+    let output = mt_crack::timestamp_seeded_output(&clock, &mut rng);
+
+    let seed = mt_crack::crack_timestamp_seed(output, &clock, 2000, 4).unwrap();
+
+    assert!((1_700_000_040..clock.now()).contains(&u64::from(seed)));
+    assert_eq!(output, Mt19937::new(seed).next_u32());
+}
+
+#[test]
+fn clone_an_mt19937_rng_from_its_output() {
+    let mut mt = Mt19937::new(1_700_000_000);
+    let outputs: Vec<_> = (0..624).map(|_| mt.next_u32()).collect();
+
+    let mut clone = mt_crack::clone_from_outputs(&outputs).unwrap();
+
+    for _ in 0..1000 {
+        assert_eq!(mt.next_u32(), clone.next_u32());
+    }
+}
+
+#[test]
+fn create_the_mt19937_stream_cipher_and_break_it() {
+    let mut rng = SplitMix64::new(24);
+    let seed = rng.gen_range(0..0x10000) as u16;
+    let ciphertext = mt_cipher::encrypt_with_random_prefix(&mut rng, seed, &[b'A'; 14]);
+
+    assert_eq!(
+        Some(seed),
+        mt_cipher::recover_seed(&ciphertext, &[b'A'; 14])
+    );
+
+    let clock = FakeClock::new(1_700_000_000);
+    let token = mt_cipher::reset_token(&clock, 16);
+    clock.sleep(30);
+
+    assert!(mt_cipher::is_time_seeded_token(&token, &clock, 3600));
+    assert!(!mt_cipher::is_time_seeded_token(
+        &rng.bytes(16),
+        &clock,
+        3600
+    ));
+}
+
+#[test]
+fn implement_a_sha1_keyed_mac() {
+    let mut rng = SplitMix64::new(28);
+    let key = rng.bytes(16);
+    let message = b"comment1=cooking%20MCs;userdata=foo;comment2=%20like%20a%20pound%20of%20bacon";
+
+    let mac = sha1::secret_prefix_mac(&key, message);
+
+    assert!(sha1::verify_secret_prefix_mac(&key, message, &mac));
+    assert!(!sha1::verify_secret_prefix_mac(
+        &key,
+        b"comment1=cooking%20MCs;userdata=foo;admin=true",
+        &mac
+    ));
+    assert!(!sha1::verify_secret_prefix_mac(
+        &rng.bytes(16),
+        message,
+        &mac
+    ));
+}
+
+#[test]
+fn break_a_sha1_keyed_mac_using_length_extension() {
+    let mut rng = SplitMix64::new(29);
+    let key_len = rng.gen_range(1..33);
+    let key = rng.bytes(key_len);
+    let message = b"comment1=cooking%20MCs;userdata=foo;comment2=%20like%20a%20pound%20of%20bacon";
+    let mac = sha1::secret_prefix_mac(&key, message);
+
+    let forgery = merkle_damgard::forge::<Sha1, _>(message, &mac, b";admin=true", 64, |m, mac| {
+        sha1::verify_secret_prefix_mac(&key, m, mac)
+    })
+    .unwrap();
+
+    assert_eq!(key_len, forgery.key_len);
+    assert!(forgery.message.starts_with(message));
+    assert!(forgery.message.ends_with(b";admin=true"));
+    assert!(sha1::verify_secret_prefix_mac(
+        &key,
+        &forgery.message,
+        &forgery.mac
+    ));
+}
+
+#[test]
+fn break_an_md4_keyed_mac_using_length_extension() {
+    let mut rng = SplitMix64::new(30);
+    let key_len = rng.gen_range(1..33);
+    let key = rng.bytes(key_len);
+    let message = b"comment1=cooking%20MCs;userdata=foo;comment2=%20like%20a%20pound%20of%20bacon";
+    let mac = md4::secret_prefix_mac(&key, message);
+
+    let forgery = merkle_damgard::forge::<Md4, _>(message, &mac, b";admin=true", 64, |m, mac| {
+        md4::verify_secret_prefix_mac(&key, m, mac)
+    })
+    .unwrap();
+
+    assert_eq!(key_len, forgery.key_len);
+    assert!(forgery.message.ends_with(b";admin=true"));
+    assert!(md4::verify_secret_prefix_mac(
+        &key,
+        &forgery.message,
+        &forgery.mac
+    ));
+}
+
+#[test]
+fn implement_and_break_hmac_sha1_with_an_artificial_timing_leak() {
+    let mut rng = SplitMix64::new(31);
+    let key = rng.bytes(16);
+    // A millisecond per byte rather than the challenge's 50ms, which already makes this
+    // challenge 32's "slightly less artificial" leak
+    let server = Server::start(&key, Duration::from_millis(1)).unwrap();
+    let client = Client::new(server.addr());
+    let check = |signature: &[u8]| client.check_signature("foo", signature).unwrap();
+
+    let recovered = timing_leak::recover_signature(20, 16, check).unwrap();
+
+    assert_eq!(hmac::hmac_sha1(&key, b"foo").to_vec(), recovered.signature);
+    assert!(check(&recovered.signature));
+}
+
+#[test]
+fn implement_diffie_hellman() {
+    let mut rng = SplitMix64::new(33);
+
+    let toy = Group::new(BigUint::from(37u32), BigUint::from(5u32));
+    let a = toy.key_pair(&mut rng);
+    let b = toy.key_pair(&mut rng);
+    assert_eq!(
+        toy.shared_secret(&a.private, &b.public),
+        toy.shared_secret(&b.private, &a.public)
+    );
+
+    let group = Group::modp_1536();
+    let a = group.key_pair(&mut rng);
+    let b = group.key_pair(&mut rng);
+    let s = group.shared_secret(&a.private, &b.public);
+    assert_eq!(s, group.shared_secret(&b.private, &a.public));
+
+    // Both sides can now talk under the key derived from the shared secret
+    let iv = rng.bytes(16);
+    let message = b"Diffie and Hellman agree";
+    let ciphertext = aes::cbc_encrypt(&dh::derive_key(&s), &iv, message).unwrap();
+    assert_eq!(
+        message.to_vec(),
+        aes::cbc_decrypt(&dh::derive_key(&s), &iv, &ciphertext).unwrap()
+    );
+}